@@ -0,0 +1,230 @@
+use crate::manifest::Manifest;
+
+use std::fs::{File, copy, create_dir_all, read_dir, remove_dir_all, remove_file};
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct BackupRecord {
+    pub created_at: u64,
+    pub cores: Vec<BackupFile>,
+    pub info: Vec<BackupFile>,
+    pub manifest: Manifest,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct BackupFile {
+    pub name: String,
+    /// Files that did not exist before the update are removed again on rollback
+    pub existed: bool,
+}
+
+/// A single generation of replaced core and info files
+pub struct Backup {
+    pub generation: String,
+    directory: PathBuf,
+    record: BackupRecord,
+}
+
+impl Backup {
+    pub fn create(core_path: &Path, manifest: &Manifest) -> Result<Backup> {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        // Generations are named after their creation time so they sort chronologically
+        let mut generation = created_at.to_string();
+        let mut suffix = 1;
+
+        while get_backups_path(core_path).join(&generation).exists() {
+            generation = format!("{}-{}", created_at, suffix);
+            suffix += 1;
+        }
+
+        let directory = get_backups_path(core_path).join(&generation);
+        create_dir_all(directory.join("cores"))?;
+        create_dir_all(directory.join("info"))?;
+
+        Ok(Backup {
+            generation,
+            directory,
+            record: BackupRecord {
+                created_at,
+                manifest: manifest.clone(),
+                ..Default::default()
+            },
+        })
+    }
+
+    pub fn open(core_path: &Path, generation: &str) -> Result<Backup> {
+        let directory = get_backups_path(core_path).join(generation);
+        let record_path = directory.join("backup.json");
+
+        if !record_path.exists() {
+            bail!("Backup generation {} not found", generation);
+        }
+
+        let reader = BufReader::new(File::open(record_path)?);
+
+        Ok(Backup {
+            generation: generation.to_owned(),
+            directory,
+            record: serde_json::from_reader(reader)?,
+        })
+    }
+
+    pub fn record(&self) -> &BackupRecord {
+        &self.record
+    }
+
+    pub fn snapshot_core(&mut self, core_path: &Path, name: &str) -> Result<()> {
+        let existed = snapshot_file(
+            &core_path.join(name),
+            &self.directory.join("cores").join(name),
+        )?;
+
+        self.record.cores.push(BackupFile {
+            name: name.to_owned(),
+            existed,
+        });
+        self.save()
+    }
+
+    pub fn snapshot_info(&mut self, info_path: &Path, name: &str) -> Result<()> {
+        let existed = snapshot_file(
+            &info_path.join(name),
+            &self.directory.join("info").join(name),
+        )?;
+
+        self.record.info.push(BackupFile {
+            name: name.to_owned(),
+            existed,
+        });
+        self.save()
+    }
+
+    pub fn restore_core(&self, core_path: &Path, name: &str) -> Result<bool> {
+        let Some(file) = self.record.cores.iter().find(|file| file.name == name) else {
+            return Ok(false);
+        };
+
+        restore_file(
+            &self.directory.join("cores").join(name),
+            &core_path.join(name),
+            file.existed,
+        )?;
+        Ok(true)
+    }
+
+    pub fn restore_info(&self, info_path: &Path, name: &str) -> Result<bool> {
+        let Some(file) = self.record.info.iter().find(|file| file.name == name) else {
+            return Ok(false);
+        };
+
+        restore_file(
+            &self.directory.join("info").join(name),
+            &info_path.join(name),
+            file.existed,
+        )?;
+        Ok(true)
+    }
+
+    /// Puts back every file of this generation together with the manifest and removes it again
+    pub fn restore_all(self, core_path: &Path, info_path: &Path) -> Result<()> {
+        for file in &self.record.cores {
+            self.restore_core(core_path, &file.name)?;
+        }
+
+        for file in &self.record.info {
+            self.restore_info(info_path, &file.name)?;
+        }
+
+        self.record.manifest.save(core_path)?;
+        self.remove()
+    }
+
+    pub fn remove(self) -> Result<()> {
+        remove_dir_all(self.directory)?;
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        let mut record_file = File::create(self.directory.join("backup.json"))?;
+        record_file.write_all(serde_json::to_string_pretty(&self.record)?.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+pub fn get_backups_path(core_path: &Path) -> PathBuf {
+    core_path.join(".starch").join("backups")
+}
+
+/// Returns all backup generations, oldest first
+pub fn list_backups(core_path: &Path) -> Result<Vec<String>> {
+    let backups_path = get_backups_path(core_path);
+    let mut generations = Vec::new();
+
+    if !backups_path.exists() {
+        return Ok(generations);
+    }
+
+    for entry in read_dir(backups_path)? {
+        let entry = entry?;
+
+        if entry.file_type()?.is_dir()
+            && let Some(name) = entry.file_name().to_str()
+        {
+            generations.push(name.to_owned());
+        }
+    }
+
+    generations.sort_by_key(|generation| {
+        let (timestamp, suffix) = generation.split_once('-').unwrap_or((generation, "0"));
+        (
+            timestamp.parse::<u64>().unwrap_or(0),
+            suffix.parse::<u64>().unwrap_or(0),
+        )
+    });
+
+    Ok(generations)
+}
+
+/// Removes the oldest backup generations until at most `keep` are left
+pub fn prune_backups(core_path: &Path, keep: usize) -> Result<()> {
+    let generations = list_backups(core_path)?;
+    let excess = generations.len().saturating_sub(keep);
+
+    for generation in &generations[..excess] {
+        remove_dir_all(get_backups_path(core_path).join(generation))?;
+    }
+
+    Ok(())
+}
+
+fn snapshot_file(source: &Path, backup: &Path) -> Result<bool> {
+    if !source.exists() {
+        return Ok(false);
+    }
+
+    create_dir_all(backup.parent().unwrap())?;
+    copy(source, backup)?;
+
+    Ok(true)
+}
+
+fn restore_file(backup: &Path, destination: &Path, existed: bool) -> Result<()> {
+    if existed {
+        create_dir_all(destination.parent().unwrap())?;
+        copy(backup, destination)?;
+    } else if destination.exists() {
+        remove_file(destination)?;
+    }
+
+    Ok(())
+}
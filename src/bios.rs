@@ -0,0 +1,164 @@
+use crate::core_info::CoreInfo;
+use crate::update_cores::{get_core_file_name, get_installed_cores};
+use crate::{get_path_from_config, get_retro_arch_config};
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum FirmwareStatus {
+    Present,
+    Missing,
+    WrongHash { expected: String, found: String },
+}
+
+pub(crate) fn check_bios(
+    core: Option<String>,
+    only_problems: bool,
+    retro_arch_path: Option<PathBuf>,
+) -> Result<()> {
+    // Get RetroArch config and load the necessary paths from it
+    let (config, retro_arch_path) = get_retro_arch_config(retro_arch_path)?;
+
+    let core_path = get_path_from_config(&config, "libretro_directory", &retro_arch_path)?;
+    let info_path = get_path_from_config(&config, "libretro_info_path", &retro_arch_path)?;
+    let system_path = get_path_from_config(&config, "system_directory", &retro_arch_path)?;
+
+    let cores = match core {
+        Some(core) => vec![get_core_file_name(&core)],
+        None => get_installed_cores(&core_path)?,
+    };
+
+    let mut missing_required = 0;
+    let mut wrong_hashes = 0;
+
+    for core in &cores {
+        let Some(info) = CoreInfo::load_for_core(&info_path, core) else {
+            continue;
+        };
+
+        if info.firmware.is_empty() {
+            continue;
+        }
+
+        // Expected hashes are only listed in the notes of the info file
+        let hashes = get_firmware_hashes(&info.notes);
+        let mut lines = Vec::new();
+
+        for firmware in &info.firmware {
+            let status = get_firmware_status(
+                &system_path.join(&firmware.path),
+                hashes.get(firmware.path.as_str()),
+            )?;
+
+            match &status {
+                FirmwareStatus::Missing if !firmware.optional => missing_required += 1,
+                FirmwareStatus::WrongHash { .. } => wrong_hashes += 1,
+                _ => {}
+            }
+
+            let problem = match status {
+                FirmwareStatus::Present => false,
+                FirmwareStatus::Missing => !firmware.optional,
+                FirmwareStatus::WrongHash { .. } => true,
+            };
+
+            if only_problems && !problem {
+                continue;
+            }
+
+            let optional = if firmware.optional { " (optional)" } else { "" };
+
+            lines.push(match status {
+                FirmwareStatus::Present => format!("  present    {}{}", firmware.path, optional),
+                FirmwareStatus::Missing => format!(
+                    "  missing    {}{} - {}",
+                    firmware.path, optional, firmware.desc
+                ),
+                FirmwareStatus::WrongHash { expected, found } => format!(
+                    "  wrong hash {}{} (expected {}, found {})",
+                    firmware.path, optional, expected, found
+                ),
+            });
+        }
+
+        if lines.is_empty() {
+            continue;
+        }
+
+        println!(
+            "{} [{}]",
+            info.display_name.as_deref().unwrap_or(core),
+            core
+        );
+
+        for line in lines {
+            println!("{}", line);
+        }
+    }
+
+    println!(
+        "{} required files missing, {} files with a wrong hash in {}.",
+        missing_required,
+        wrong_hashes,
+        system_path.display()
+    );
+
+    Ok(())
+}
+
+/// Notes list hashes in the format "(!) scph5501.bin (md5): 490f666e1afb15b7362b406ed1cea246"
+fn get_firmware_hashes(notes: &[String]) -> HashMap<&str, String> {
+    notes
+        .iter()
+        .filter_map(|note| {
+            let note = note.trim().strip_prefix("(!)")?.trim();
+            let (path, hash) = note.split_once("(md5):")?;
+
+            Some((path.trim(), hash.trim().to_lowercase()))
+        })
+        .collect()
+}
+
+fn get_firmware_status(path: &Path, expected_hash: Option<&String>) -> Result<FirmwareStatus> {
+    if !path.is_file() {
+        return Ok(FirmwareStatus::Missing);
+    }
+
+    let Some(expected) = expected_hash else {
+        return Ok(FirmwareStatus::Present);
+    };
+
+    let found = get_file_md5(path)?;
+
+    if found == *expected {
+        Ok(FirmwareStatus::Present)
+    } else {
+        Ok(FirmwareStatus::WrongHash {
+            expected: expected.to_owned(),
+            found,
+        })
+    }
+}
+
+fn get_file_md5(path: &Path) -> Result<String> {
+    let mut file = File::open(path)?;
+    let mut context = md5::Context::new();
+    let mut buffer = [0u8; 8192];
+
+    loop {
+        let read_size = file.read(&mut buffer)?;
+
+        if read_size == 0 {
+            break;
+        }
+
+        context.consume(&buffer[..read_size]);
+    }
+
+    Ok(format!("{:x}", context.finalize()))
+}
@@ -0,0 +1,174 @@
+use crate::download::{DownloadOptions, fetch_cached};
+use crate::frontend::is_older_version;
+use crate::target::Target;
+
+use std::fs::read_to_string;
+
+use anyhow::{Context, Result};
+use ini::Ini;
+use reqwest::Client;
+
+const DEFAULT_BUILDBOT_URL: &str = "https://buildbot.libretro.com";
+
+/// Location of the libretro buildbot or a mirror with the same directory layout
+#[derive(Clone, Debug)]
+pub struct Buildbot {
+    base_url: String,
+    assets_url: String,
+}
+
+impl Buildbot {
+    /// Uses the buildbot URLs from the RetroArch config unless a mirror was given
+    pub fn new(config: Option<&Ini>, mirror: Option<&str>) -> Buildbot {
+        if let Some(mirror) = mirror {
+            let base_url = mirror.trim_end_matches('/').to_owned();
+
+            return Buildbot {
+                assets_url: format!("{}/assets", base_url),
+                base_url,
+            };
+        }
+
+        let get_url = |key: &str| {
+            config
+                .and_then(|config| config.get_from(None::<String>, key))
+                .filter(|url| !url.is_empty())
+                .map(|url| upgrade_to_https(url.trim_end_matches('/')))
+        };
+
+        // The configured cores URL points to a platform directory like ".../nightly/linux/x86_64/latest"
+        let base_url = get_url("core_updater_buildbot_cores_url")
+            .and_then(|url| {
+                ["/nightly/", "/stable/"].iter().find_map(|channel| {
+                    url.find(channel).map(|position| url[..position].to_owned())
+                })
+            })
+            .unwrap_or_else(|| DEFAULT_BUILDBOT_URL.to_owned());
+
+        let assets_url = get_url("core_updater_buildbot_assets_url")
+            .unwrap_or_else(|| format!("{}/assets", base_url));
+
+        Buildbot {
+            base_url,
+            assets_url,
+        }
+    }
+
+    /// Base URL for the given release channel of RetroArch cores built for the target platform
+    pub fn cores_url(&self, channel: &str, target: &Target) -> String {
+        let release_type = if channel != "nightly" {
+            format!("stable/{}", channel)
+        } else {
+            channel.to_owned()
+        };
+
+        format!(
+            "{}/{}/{}",
+            self.base_url, release_type, target.buildbot_path
+        )
+    }
+
+    /// Directory listing of all stable releases
+    pub fn stable_url(&self) -> String {
+        format!("{}/stable/", self.base_url)
+    }
+
+    /// URL of a file in the frontend assets directory (e.g. info.zip)
+    pub fn frontend_asset_url(&self, file_name: &str) -> String {
+        format!("{}/frontend/{}", self.assets_url, file_name)
+    }
+}
+
+fn upgrade_to_https(url: &str) -> String {
+    match url.strip_prefix("http://") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_owned(),
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IndexEntry {
+    pub date: String,
+    pub crc: u32,
+    pub file_name: String,
+}
+
+impl IndexEntry {
+    /// Name of the core file contained in the archive this entry points to
+    pub fn core_name(&self) -> &str {
+        self.file_name
+            .strip_suffix(".zip")
+            .unwrap_or(&self.file_name)
+    }
+}
+
+/// Fetches the .index-extended listing of the cores available below the given cores URL
+pub(crate) async fn fetch_index(
+    client: &Client,
+    cores_url: &str,
+    options: &DownloadOptions,
+) -> Result<Vec<IndexEntry>> {
+    let url = format!("{}/latest/.index-extended", cores_url);
+    let index_path = fetch_cached(client, &url, "Downloading core index...", options).await?;
+    let body = read_to_string(index_path)?;
+
+    parse_index(&body).with_context(|| format!("Failed to parse buildbot index at {}", url))
+}
+
+/// Finds the newest stable release in the buildbot's directory listing
+pub(crate) async fn fetch_latest_stable_version(
+    client: &Client,
+    buildbot: &Buildbot,
+    options: &DownloadOptions,
+) -> Result<Option<String>> {
+    let listing_path = fetch_cached(
+        client,
+        &buildbot.stable_url(),
+        "Checking for the latest stable release...",
+        options,
+    )
+    .await?;
+    let body = read_to_string(listing_path)?;
+
+    // Every release is a directory named after its version, e.g. <a href="1.19.1/">
+    let latest = body
+        .split("href=\"")
+        .skip(1)
+        .filter_map(|link| link.split('"').next())
+        .filter_map(|link| link.trim_end_matches('/').rsplit('/').next())
+        .filter(|name| {
+            name.contains('.') && name.split('.').all(|part| part.parse::<u32>().is_ok())
+        })
+        .reduce(|latest, version| {
+            if is_older_version(latest, version) {
+                version
+            } else {
+                latest
+            }
+        });
+
+    Ok(latest.map(str::to_owned))
+}
+
+fn parse_index(body: &str) -> Result<Vec<IndexEntry>> {
+    // Every line has the format "<date> <crc32> <file name>"
+    body.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let mut parts = line.split_whitespace();
+
+            let (Some(date), Some(crc), Some(file_name)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                anyhow::bail!("Malformed index line {:?}", line);
+            };
+
+            Ok(IndexEntry {
+                date: date.to_owned(),
+                crc: u32::from_str_radix(crc, 16)
+                    .with_context(|| format!("Invalid CRC in index line {:?}", line))?,
+                file_name: file_name.to_owned(),
+            })
+        })
+        .collect()
+}
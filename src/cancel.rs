@@ -0,0 +1,66 @@
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::process::exit;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use anyhow::Result;
+
+/// Exit status used when an operation was interrupted (128 + SIGINT, like shells do)
+pub(crate) const CANCELLED_EXIT_CODE: u8 = 130;
+
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Error returned by operations that stopped because the user pressed Ctrl-C
+#[derive(Debug)]
+pub(crate) struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Operation cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Turns Ctrl-C into a cancellation request, so running operations can clean up after themselves.
+/// Pressing Ctrl-C a second time exits right away.
+pub(crate) fn listen_for_cancellation() {
+    tokio::spawn(async {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if CANCELLED.swap(true, Ordering::SeqCst) {
+                exit(CANCELLED_EXIT_CODE.into());
+            }
+
+            eprintln!("\nCancelling, press Ctrl-C again to exit immediately...");
+        }
+    });
+}
+
+pub(crate) fn is_cancelled() -> bool {
+    CANCELLED.load(Ordering::SeqCst)
+}
+
+/// Fails with `Cancelled` once the user asked to stop, meant to be called between units of work
+pub(crate) fn check_cancelled() -> Result<()> {
+    if is_cancelled() {
+        Err(Cancelled.into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Runs the given future until it completes or the user cancels
+pub(crate) async fn cancellable<F: Future>(future: F) -> Result<F::Output> {
+    tokio::select! {
+        output = future => Ok(output),
+        _ = cancelled() => Err(Cancelled.into()),
+    }
+}
+
+async fn cancelled() {
+    // Only the listener receives the signal, everyone else polls the flag it sets
+    while !is_cancelled() {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
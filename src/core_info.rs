@@ -0,0 +1,107 @@
+use std::path::Path;
+
+use anyhow::Result;
+use ini::Ini;
+
+/// Metadata of a core as described by its libretro .info file
+#[derive(Clone, Debug, Default)]
+pub struct CoreInfo {
+    pub display_name: Option<String>,
+    pub display_version: Option<String>,
+    pub systemname: Option<String>,
+    pub manufacturer: Option<String>,
+    pub supported_extensions: Vec<String>,
+    pub database: Vec<String>,
+    pub required_hw_api: Vec<String>,
+    pub licenses: Vec<String>,
+    pub notes: Vec<String>,
+    pub firmware: Vec<Firmware>,
+}
+
+/// A BIOS or firmware file a core needs or can make use of
+#[derive(Clone, Debug, Default)]
+pub struct Firmware {
+    /// Path relative to RetroArch's system directory
+    pub path: String,
+    pub desc: String,
+    pub optional: bool,
+}
+
+impl CoreInfo {
+    pub fn load(path: &Path) -> Result<CoreInfo> {
+        let info = Ini::load_from_file_noescape(path)?;
+        let get = |key: &str| {
+            info.get_from(None::<String>, key)
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_owned)
+        };
+        let get_list = |key: &str| get(key).map(|value| split_list(&value)).unwrap_or_default();
+
+        // Firmware entries are numbered from 0 to firmware_count - 1
+        let firmware_count: usize = get("firmware_count")
+            .and_then(|count| count.parse().ok())
+            .unwrap_or(0);
+
+        let firmware = (0..firmware_count)
+            .filter_map(|index| {
+                Some(Firmware {
+                    path: get(&format!("firmware{}_path", index))?,
+                    desc: get(&format!("firmware{}_desc", index)).unwrap_or_default(),
+                    optional: get(&format!("firmware{}_opt", index))
+                        .is_some_and(|optional| optional == "true"),
+                })
+            })
+            .collect();
+
+        Ok(CoreInfo {
+            display_name: get("display_name"),
+            display_version: get("display_version"),
+            systemname: get("systemname"),
+            manufacturer: get("manufacturer"),
+            supported_extensions: get_list("supported_extensions")
+                .into_iter()
+                .map(|extension| extension.to_lowercase())
+                .collect(),
+            database: get_list("database"),
+            required_hw_api: get_list("required_hw_api"),
+            licenses: get_list("license"),
+            notes: get_list("notes"),
+            firmware,
+        })
+    }
+
+    /// Loads the info file belonging to an installed core if there is one
+    pub fn load_for_core(info_path: &Path, core: &str) -> Option<CoreInfo> {
+        let path = info_path.join(get_info_file_name(core));
+
+        if !path.exists() {
+            return None;
+        }
+
+        CoreInfo::load(&path).ok()
+    }
+
+    pub fn supports_extension(&self, extension: &str) -> bool {
+        let extension = extension.trim_start_matches('.').to_lowercase();
+        self.supported_extensions.contains(&extension)
+    }
+}
+
+/// Returns the name of the info file belonging to a core file
+pub(crate) fn get_info_file_name(core: &str) -> String {
+    Path::new(core)
+        .with_extension("info")
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Info files separate multiple values with a pipe character
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split('|')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
@@ -0,0 +1,149 @@
+use crate::core_info::CoreInfo;
+use crate::update_cores::ChangeStatus;
+
+use std::collections::HashMap;
+use std::fs::{File, metadata};
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use indicatif::HumanBytes;
+use serde::Serialize;
+
+/// Version and size of an installed core at one point of an update
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CoreState {
+    pub version: Option<String>,
+    pub size: Option<u64>,
+}
+
+impl CoreState {
+    pub fn read(core_path: &Path, info_path: &Path, core: &str) -> CoreState {
+        CoreState {
+            version: CoreInfo::load_for_core(info_path, core).and_then(|info| info.display_version),
+            size: metadata(core_path.join(core))
+                .ok()
+                .map(|metadata| metadata.len()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct CoreChange {
+    pub name: String,
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+    pub old_size: Option<u64>,
+    pub new_size: Option<u64>,
+    pub size_delta: i64,
+}
+
+/// Summary of what an update did to the installed cores
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct CoreReport {
+    pub created_at: u64,
+    pub channel: String,
+    pub added: Vec<CoreChange>,
+    pub updated: Vec<CoreChange>,
+    pub removed_upstream: Vec<String>,
+    pub unchanged: Vec<String>,
+}
+
+impl CoreReport {
+    /// Compares the installed cores against their state from before the update
+    pub fn create(
+        channel: &str,
+        cores: &[(&str, ChangeStatus)],
+        before: &HashMap<String, CoreState>,
+        removed_upstream: Vec<String>,
+        core_path: &Path,
+        info_path: &Path,
+    ) -> CoreReport {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        let mut report = CoreReport {
+            created_at,
+            channel: channel.to_owned(),
+            added: Vec::new(),
+            updated: Vec::new(),
+            removed_upstream,
+            unchanged: Vec::new(),
+        };
+
+        for (core, status) in cores {
+            if *status == ChangeStatus::Unchanged {
+                report.unchanged.push(core.to_string());
+                continue;
+            }
+
+            let old = before.get(*core).cloned().unwrap_or_default();
+            let new = CoreState::read(core_path, info_path, core);
+
+            let change = CoreChange {
+                name: core.to_string(),
+                size_delta: new.size.unwrap_or(0) as i64 - old.size.unwrap_or(0) as i64,
+                old_version: old.version,
+                new_version: new.version,
+                old_size: old.size,
+                new_size: new.size,
+            };
+
+            match status {
+                ChangeStatus::New => report.added.push(change),
+                _ => report.updated.push(change),
+            }
+        }
+
+        report
+    }
+
+    pub fn print(&self) {
+        println!("Update report:");
+
+        for change in &self.added {
+            println!(
+                "  added:     {} {} ({})",
+                change.name,
+                change.new_version.as_deref().unwrap_or("unknown version"),
+                HumanBytes(change.new_size.unwrap_or(0))
+            );
+        }
+
+        for change in &self.updated {
+            let sign = if change.size_delta < 0 { "-" } else { "+" };
+
+            println!(
+                "  updated:   {} {} -> {} ({}{})",
+                change.name,
+                change.old_version.as_deref().unwrap_or("unknown version"),
+                change.new_version.as_deref().unwrap_or("unknown version"),
+                sign,
+                HumanBytes(change.size_delta.unsigned_abs())
+            );
+        }
+
+        for core in &self.removed_upstream {
+            println!("  removed:   {} (no longer on the buildbot)", core);
+        }
+
+        println!(
+            "{} added, {} updated, {} removed upstream, {} unchanged",
+            self.added.len(),
+            self.updated.len(),
+            self.removed_upstream.len(),
+            self.unchanged.len()
+        );
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+
+        println!("Update report written to {}.", path.display());
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use crate::core_info::CoreInfo;
+use crate::update_cores::get_installed_cores;
+use crate::{get_path_from_config, get_retro_arch_config};
+
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+
+pub(crate) fn find_cores(
+    file: Option<PathBuf>,
+    extension: Option<String>,
+    all: bool,
+    retro_arch_path: Option<PathBuf>,
+) -> Result<()> {
+    // Use the extension of the given file unless one was explicitly requested
+    let Some(extension) = extension.or_else(|| {
+        file.as_deref()
+            .and_then(Path::extension)
+            .and_then(|extension| extension.to_str())
+            .map(str::to_owned)
+    }) else {
+        bail!("Specify either a file or an extension to search for");
+    };
+
+    // Get RetroArch config and load the necessary paths from it
+    let (config, retro_arch_path) = get_retro_arch_config(retro_arch_path)?;
+
+    let core_path = get_path_from_config(&config, "libretro_directory", &retro_arch_path)?;
+    let info_path = get_path_from_config(&config, "libretro_info_path", &retro_arch_path)?;
+
+    let installed_cores = get_installed_cores(&core_path)?;
+
+    // Either look at every available info file or only at those of installed cores
+    let mut candidates = Vec::new();
+
+    if all {
+        for entry in read_dir(&info_path)? {
+            let path = entry?.path();
+
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "info")
+            {
+                candidates.push(path);
+            }
+        }
+    } else {
+        for core in &installed_cores {
+            candidates.push(info_path.join(Path::new(core).with_extension("info")));
+        }
+    }
+
+    candidates.sort();
+
+    let mut found = false;
+
+    for path in candidates.iter().filter(|path| path.exists()) {
+        let info = CoreInfo::load(path)?;
+
+        if !info.supports_extension(&extension) {
+            continue;
+        }
+
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let installed = installed_cores
+            .iter()
+            .any(|core| Path::new(core).file_stem() == path.file_stem());
+
+        println!(
+            "{:<40} {}{}",
+            name,
+            info.display_name.as_deref().unwrap_or_default(),
+            if all && installed { " (installed)" } else { "" }
+        );
+
+        found = true;
+    }
+
+    if !found {
+        println!(
+            "No {} cores support .{} files.",
+            if all { "known" } else { "installed" },
+            extension.trim_start_matches('.')
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use crate::core_info::{CoreInfo, get_info_file_name};
+use crate::update_cores::get_core_file_name;
+use crate::{get_path_from_config, get_retro_arch_config};
+
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+
+pub(crate) fn show_core_info(core: &str, retro_arch_path: Option<PathBuf>) -> Result<()> {
+    // Get RetroArch config and load the necessary paths from it
+    let (config, retro_arch_path) = get_retro_arch_config(retro_arch_path)?;
+
+    let core_path = get_path_from_config(&config, "libretro_directory", &retro_arch_path)?;
+    let info_path = get_path_from_config(&config, "libretro_info_path", &retro_arch_path)?;
+
+    let core = get_core_file_name(core);
+    let info_file_path = info_path.join(get_info_file_name(&core));
+
+    if !info_file_path.exists() {
+        bail!("No info file found for {} in {}", core, info_path.display());
+    }
+
+    let info = CoreInfo::load(&info_file_path)?;
+    let unset = || "-".to_owned();
+
+    println!("Core:            {}", core);
+    println!(
+        "Installed:       {}",
+        if core_path.join(&core).exists() {
+            "yes"
+        } else {
+            "no"
+        }
+    );
+    println!(
+        "Name:            {}",
+        info.display_name.unwrap_or_else(unset)
+    );
+    println!(
+        "Version:         {}",
+        info.display_version.unwrap_or_else(unset)
+    );
+    println!("System:          {}", info.systemname.unwrap_or_else(unset));
+    println!(
+        "Manufacturer:    {}",
+        info.manufacturer.unwrap_or_else(unset)
+    );
+    println!("Extensions:      {}", info.supported_extensions.join(", "));
+    println!("Databases:       {}", info.database.join(", "));
+    println!("Required HW API: {}", info.required_hw_api.join(", "));
+    println!("Licenses:        {}", info.licenses.join(", "));
+
+    if !info.firmware.is_empty() {
+        println!("Firmware:");
+
+        for firmware in &info.firmware {
+            println!(
+                "  {} - {}{}",
+                firmware.path,
+                firmware.desc,
+                if firmware.optional { " (optional)" } else { "" }
+            );
+        }
+    }
+
+    if !info.notes.is_empty() {
+        println!("Notes:");
+
+        for note in &info.notes {
+            println!("  {}", note);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use crate::buildbot::{Buildbot, fetch_index};
+use crate::download::{DownloadOptions, create_client, fetch_all_cached};
+use crate::manifest::Manifest;
+use crate::steam::get_steam_cores;
+use crate::target::Target;
+use crate::update_cores::{
+    ChangeStatus, CoreInstall, extract_zip_file, get_core_file_name, get_file_crc,
+    plan_zip_extraction,
+};
+use crate::{get_path_from_config, get_retro_arch_config};
+
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+
+pub(crate) async fn install_core(
+    core: &str,
+    version: String,
+    mirror: Option<String>,
+    replace_steam_core: bool,
+    keep_backups: usize,
+    download_options: DownloadOptions,
+    retro_arch_path: Option<PathBuf>,
+) -> Result<()> {
+    // Get RetroArch config and load the necessary paths from it
+    let (config, retro_arch_path) = get_retro_arch_config(retro_arch_path)?;
+
+    let core_path = get_path_from_config(&config, "libretro_directory", &retro_arch_path)?;
+    let info_path = get_path_from_config(&config, "libretro_info_path", &retro_arch_path)?;
+
+    let core = get_core_file_name(core);
+    let mut manifest = Manifest::load(&core_path)?;
+
+    if let Some(pin) = manifest.pins.get(&core) {
+        bail!("{} is {}, unpin it first", core, pin.describe());
+    }
+
+    if !replace_steam_core && get_steam_cores(&core_path).contains(&core) {
+        bail!(
+            "{} is managed by Steam, use --replace-steam-core to replace it anyway",
+            core
+        );
+    }
+
+    // Look the core up in the buildbot index of the requested channel
+    let buildbot = Buildbot::new(Some(&config), mirror.as_deref());
+    let client = create_client(&download_options)?;
+
+    let target = Target::find(None, None)?;
+    let cores_url = buildbot.cores_url(&version, &target);
+    let index = fetch_index(&client, &cores_url, &download_options).await?;
+
+    let Some(entry) = index.iter().find(|entry| entry.core_name() == core) else {
+        bail!("{} is not available on the {} buildbot", core, version);
+    };
+
+    let installed_core_path = core_path.join(&core);
+
+    if installed_core_path.exists() && get_file_crc(&installed_core_path)? == entry.crc {
+        println!("{} is already installed and up to date.", core);
+        return Ok(());
+    }
+
+    let core_download_url = format!("{}/latest/{}", cores_url, entry.file_name);
+    let info_download_url = buildbot.frontend_asset_url("info.zip");
+
+    let download_file_paths = fetch_all_cached(
+        &client,
+        &[
+            (
+                core_download_url.to_owned(),
+                format!("Downloading {}...", core),
+            ),
+            (
+                info_download_url.to_owned(),
+                "Downloading info files...".to_owned(),
+            ),
+        ],
+        &download_options,
+    )
+    .await?;
+    let core_download_file_path = &download_file_paths[&core_download_url];
+    let info_download_file_path = &download_file_paths[&info_download_url];
+
+    let info_changes: Vec<PathBuf> = plan_zip_extraction(info_download_file_path, &info_path)?
+        .into_iter()
+        .filter(|(_, status)| *status != ChangeStatus::Unchanged)
+        .map(|(path, _)| path)
+        .collect();
+
+    let status = if installed_core_path.exists() {
+        ChangeStatus::Updated
+    } else {
+        ChangeStatus::New
+    };
+
+    let core_install = CoreInstall {
+        channel: &version,
+        core_path: &core_path,
+        info_path: &info_path,
+        target,
+        keep_backups,
+        report: None,
+    };
+
+    core_install.run(
+        &mut manifest,
+        &[(&core, status)],
+        &info_changes,
+        vec![],
+        |manifest| {
+            extract_zip_file(
+                core_download_file_path,
+                &core_path,
+                &format!("Extracting {}...", core),
+            )?;
+
+            manifest.record_install(&core, &core_download_url, &version, &entry.date, entry.crc);
+            manifest.save(&core_path)?;
+
+            if !info_changes.is_empty() {
+                extract_zip_file(
+                    info_download_file_path,
+                    &info_path,
+                    "Extracting info files...",
+                )?;
+            }
+
+            Ok(())
+        },
+    )?;
+
+    println!("{} successfully installed.", core);
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use crate::core_info::CoreInfo;
+use crate::manifest::Manifest;
+use crate::steam::{get_core_owner, get_steam_cores};
+use crate::update_cores::get_installed_cores;
+use crate::{get_path_from_config, get_retro_arch_config};
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+pub(crate) fn list_cores(retro_arch_path: Option<PathBuf>) -> Result<()> {
+    // Get RetroArch config and load the necessary paths from it
+    let (config, retro_arch_path) = get_retro_arch_config(retro_arch_path)?;
+
+    let core_path = get_path_from_config(&config, "libretro_directory", &retro_arch_path)?;
+    let info_path = get_path_from_config(&config, "libretro_info_path", &retro_arch_path)?;
+
+    let manifest = Manifest::load(&core_path)?;
+    let installed_cores = get_installed_cores(&core_path)?;
+    let steam_cores = get_steam_cores(&core_path);
+
+    if installed_cores.is_empty() {
+        println!("No cores installed in {}.", core_path.display());
+        return Ok(());
+    }
+
+    for core in &installed_cores {
+        let info = CoreInfo::load_for_core(&info_path, core).unwrap_or_default();
+
+        let display_name = info.display_name.as_deref().unwrap_or("(no info file)");
+        let version = info.display_version.as_deref().unwrap_or("-");
+
+        let pin = manifest
+            .pins
+            .get(core)
+            .map(|pin| format!(" [{}]", pin.describe()))
+            .unwrap_or_default();
+
+        let owner = get_core_owner(core, &steam_cores, &manifest);
+
+        println!(
+            "{:<40} {:<6} {:<50} {}{}",
+            core, owner, display_name, version, pin
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,6 @@
+pub mod find;
+pub mod info;
+pub mod install;
+pub mod list;
+pub mod remove;
+pub mod verify;
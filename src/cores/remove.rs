@@ -0,0 +1,47 @@
+use crate::core_info::get_info_file_name;
+use crate::manifest::Manifest;
+use crate::update_cores::get_core_file_name;
+use crate::{get_path_from_config, get_retro_arch_config};
+
+use std::fs::remove_file;
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+
+pub(crate) fn remove_core(
+    core: &str,
+    remove_info: bool,
+    retro_arch_path: Option<PathBuf>,
+) -> Result<()> {
+    // Get RetroArch config and load the necessary paths from it
+    let (config, retro_arch_path) = get_retro_arch_config(retro_arch_path)?;
+
+    let core_path = get_path_from_config(&config, "libretro_directory", &retro_arch_path)?;
+    let info_path = get_path_from_config(&config, "libretro_info_path", &retro_arch_path)?;
+
+    let core = get_core_file_name(core);
+    let installed_core_path = core_path.join(&core);
+
+    if !installed_core_path.exists() {
+        bail!("{} is not installed", core);
+    }
+
+    remove_file(installed_core_path)?;
+
+    if remove_info {
+        let installed_info_path = info_path.join(get_info_file_name(&core));
+
+        if installed_info_path.exists() {
+            remove_file(installed_info_path)?;
+        }
+    }
+
+    // Forget everything starch knew about the core
+    let mut manifest = Manifest::load(&core_path)?;
+    manifest.cores.remove(&core);
+    manifest.pins.remove(&core);
+    manifest.save(&core_path)?;
+
+    println!("{} removed.", core);
+    Ok(())
+}
@@ -0,0 +1,263 @@
+use crate::target::Target;
+use crate::update_cores::{get_core_file_name, list_core_files};
+use crate::{get_path_from_config, get_retro_arch_config};
+
+use std::fs::read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use goblin::Object;
+use goblin::elf::header::{EM_386, EM_AARCH64, EM_ARM, EM_X86_64};
+use goblin::mach::cputype::{CPU_TYPE_ARM64, CPU_TYPE_X86, CPU_TYPE_X86_64};
+use goblin::mach::{Mach, MachO, SingleArch};
+use goblin::pe::header::{
+    COFF_MACHINE_ARM64, COFF_MACHINE_ARMNT, COFF_MACHINE_X86, COFF_MACHINE_X86_64,
+};
+
+/// Library extensions of all platforms, so cores copied over from another platform are found too
+const CORE_EXTENSIONS: [&str; 3] = ["so", "dll", "dylib"];
+
+/// Entry point every libretro core has to export
+const API_VERSION_SYMBOL: &str = "retro_api_version";
+
+pub(crate) fn verify_cores(core: Option<&str>, retro_arch_path: Option<PathBuf>) -> Result<()> {
+    // Get RetroArch config and load the necessary paths from it
+    let (config, retro_arch_path) = get_retro_arch_config(retro_arch_path)?;
+
+    let core_path = get_path_from_config(&config, "libretro_directory", &retro_arch_path)?;
+    let target = Target::find(None, None)?;
+
+    let cores = match core {
+        Some(core) => {
+            let core = get_core_file_name(core);
+
+            if !core_path.join(&core).exists() {
+                bail!("{} is not installed", core);
+            }
+
+            vec![core]
+        }
+        None => {
+            let mut cores = Vec::new();
+
+            for extension in CORE_EXTENSIONS {
+                cores.extend(list_core_files(&core_path, extension)?);
+            }
+
+            cores.sort();
+            cores
+        }
+    };
+
+    let mut broken_cores = 0;
+
+    for core in &cores {
+        let problems = check_core(&core_path.join(core), &target)?;
+
+        if problems.is_empty() {
+            if cores.len() == 1 {
+                println!("{}: ok", core);
+            }
+        } else {
+            println!("{}: {}", core, problems.join(", "));
+            broken_cores += 1;
+        }
+    }
+
+    println!(
+        "{} cores verified, {} with problems",
+        cores.len(),
+        broken_cores
+    );
+
+    if broken_cores > 0 {
+        bail!("{} cores can't be loaded by RetroArch", broken_cores);
+    }
+
+    Ok(())
+}
+
+/// Checks freshly installed cores and warns about the ones RetroArch won't be able to load.
+/// The update already happened at this point, so cores that can't be checked only cause a warning too.
+pub(crate) fn warn_about_broken_cores(core_path: &Path, cores: &[&str], target: &Target) {
+    for core in cores {
+        match check_core(&core_path.join(core), target) {
+            Ok(problems) if !problems.is_empty() => {
+                println!("Warning: {}: {}", core, problems.join(", "));
+            }
+            Ok(_) => {}
+            Err(error) => println!("Warning: Could not check {}: {:#}", core, error),
+        }
+    }
+}
+
+/// Describes everything that keeps the core at the given path from being loaded on the target
+pub(crate) fn check_core(path: &Path, target: &Target) -> Result<Vec<String>> {
+    let mut problems = Vec::new();
+
+    if path
+        .extension()
+        .is_some_and(|extension| extension != target.core_extension)
+    {
+        problems.push(format!(
+            "unexpected file extension (expected .{})",
+            target.core_extension
+        ));
+    }
+
+    let bytes = read(path)?;
+
+    if bytes.is_empty() {
+        problems.push("empty file".to_owned());
+        return Ok(problems);
+    }
+
+    let binary = match inspect_binary(&bytes, target) {
+        Ok(binary) => binary,
+        Err(error) => {
+            problems.push(format!("not a valid library ({})", error));
+            return Ok(problems);
+        }
+    };
+
+    if binary.os != target.os || binary.arch != target.arch {
+        problems.push(format!(
+            "built for {}/{} (expected {})",
+            binary.os, binary.arch, target
+        ));
+    }
+
+    if binary.truncated {
+        problems.push("file is truncated".to_owned());
+    }
+
+    if !binary.exports_api_version {
+        problems.push(format!("does not export {}", API_VERSION_SYMBOL));
+    }
+
+    Ok(problems)
+}
+
+/// What the headers of a core library tell about it
+struct BinaryInfo {
+    os: &'static str,
+    arch: String,
+    truncated: bool,
+    exports_api_version: bool,
+}
+
+fn inspect_binary(bytes: &[u8], target: &Target) -> Result<BinaryInfo, String> {
+    let length = bytes.len() as u64;
+
+    match Object::parse(bytes).map_err(|error| error.to_string())? {
+        Object::Elf(elf) => {
+            let arch = match elf.header.e_machine {
+                EM_X86_64 => "x86_64".to_owned(),
+                EM_386 => "x86".to_owned(),
+                EM_ARM => "armhf".to_owned(),
+                EM_AARCH64 => "aarch64".to_owned(),
+                machine => format!("machine {}", machine),
+            };
+
+            let truncated = elf
+                .program_headers
+                .iter()
+                .any(|header| header.p_offset + header.p_filesz > length);
+
+            let exports_api_version = elf.dynsyms.iter().any(|symbol| {
+                symbol.st_shndx != 0
+                    && elf.dynstrtab.get_at(symbol.st_name) == Some(API_VERSION_SYMBOL)
+            });
+
+            Ok(BinaryInfo {
+                os: "linux",
+                arch,
+                truncated,
+                exports_api_version,
+            })
+        }
+        Object::PE(pe) => {
+            let arch = match pe.header.coff_header.machine {
+                COFF_MACHINE_X86_64 => "x86_64".to_owned(),
+                COFF_MACHINE_X86 => "x86".to_owned(),
+                COFF_MACHINE_ARMNT => "armhf".to_owned(),
+                COFF_MACHINE_ARM64 => "aarch64".to_owned(),
+                machine => format!("machine {:#x}", machine),
+            };
+
+            let truncated = pe.sections.iter().any(|section| {
+                section.pointer_to_raw_data as u64 + section.size_of_raw_data as u64 > length
+            });
+
+            let exports_api_version = pe
+                .exports
+                .iter()
+                .any(|export| export.name == Some(API_VERSION_SYMBOL));
+
+            Ok(BinaryInfo {
+                os: "windows",
+                arch,
+                truncated,
+                exports_api_version,
+            })
+        }
+        Object::Mach(Mach::Binary(macho)) => inspect_macho(&macho, length),
+        Object::Mach(Mach::Fat(multi_arch)) => {
+            // Universal binaries only have to contain a slice for the target architecture
+            let mut slices = Vec::new();
+
+            for slice in &multi_arch {
+                if let SingleArch::MachO(macho) = slice.map_err(|error| error.to_string())? {
+                    slices.push(inspect_macho(&macho, u64::MAX)?);
+                }
+            }
+
+            let arches: Vec<String> = slices.iter().map(|slice| slice.arch.clone()).collect();
+            let position = slices
+                .iter()
+                .position(|slice| slice.arch == target.arch)
+                .unwrap_or(0);
+
+            if slices.is_empty() {
+                return Err("universal binary without Mach-O slices".to_owned());
+            }
+
+            let mut slice = slices.swap_remove(position);
+
+            if slice.arch != target.arch {
+                slice.arch = arches.join("+");
+            }
+
+            Ok(slice)
+        }
+        _ => Err("unknown file format".to_owned()),
+    }
+}
+
+fn inspect_macho(macho: &MachO, length: u64) -> Result<BinaryInfo, String> {
+    let arch = match macho.header.cputype {
+        CPU_TYPE_X86_64 => "x86_64".to_owned(),
+        CPU_TYPE_ARM64 => "aarch64".to_owned(),
+        CPU_TYPE_X86 => "x86".to_owned(),
+        cputype => format!("cpu type {}", cputype),
+    };
+
+    let truncated = macho
+        .segments
+        .iter()
+        .any(|segment| segment.fileoff + segment.filesize > length);
+
+    // Mach-O symbols carry a leading underscore
+    let exports_api_version = macho
+        .exports()
+        .map_err(|error| error.to_string())?
+        .iter()
+        .any(|export| export.name.strip_prefix('_') == Some(API_VERSION_SYMBOL));
+
+    Ok(BinaryInfo {
+        os: "macos",
+        arch,
+        truncated,
+        exports_api_version,
+    })
+}
@@ -0,0 +1,368 @@
+use crate::cancel::cancellable;
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions, create_dir_all, remove_file, rename};
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+use clap::Args;
+use futures_util::{StreamExt, TryStreamExt, stream};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use reqwest::header::{
+    ETAG, HeaderMap, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, LAST_MODIFIED, RANGE,
+};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+
+#[derive(Args, Clone, Copy, Debug)]
+pub struct DownloadOptions {
+    #[arg(
+        long,
+        help = "Network timeout in seconds for connecting and reading downloads",
+        default_value_t = 30
+    )]
+    pub timeout: u64,
+
+    #[arg(
+        long,
+        help = "How often a failed download is retried before giving up",
+        default_value_t = 5
+    )]
+    pub retries: u32,
+
+    #[arg(
+        long,
+        help = "Only use previously downloaded archives from the download cache"
+    )]
+    pub offline: bool,
+
+    #[arg(
+        long,
+        help = "Number of files downloaded at the same time",
+        default_value_t = 4
+    )]
+    pub jobs: usize,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+struct CacheEntry {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl CacheEntry {
+    /// Whether the server sent anything that identifies this version of the file
+    fn is_validated(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+}
+
+/// Outcome of a conditional download
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Download {
+    Complete,
+    NotModified,
+}
+
+pub(crate) fn create_client(options: &DownloadOptions) -> Result<Client> {
+    Ok(Client::builder()
+        .connect_timeout(Duration::from_secs(options.timeout))
+        .read_timeout(Duration::from_secs(options.timeout))
+        .build()?)
+}
+
+/// Downloads a file into the persistent download cache and returns the path of the cached copy.
+/// Unchanged files are not downloaded again thanks to conditional requests.
+pub(crate) async fn fetch_cached(
+    client: &Client,
+    url: &str,
+    message: &str,
+    options: &DownloadOptions,
+) -> Result<PathBuf> {
+    fetch_cached_with_progress(client, url, message, options, &MultiProgress::new()).await
+}
+
+/// Downloads several files into the download cache at once (up to --jobs at a time).
+/// Takes pairs of URL and progress message and returns the cached copies by URL.
+pub(crate) async fn fetch_all_cached(
+    client: &Client,
+    downloads: &[(String, String)],
+    options: &DownloadOptions,
+) -> Result<HashMap<String, PathBuf>> {
+    let progress = MultiProgress::new();
+
+    // Overall progress is only worth showing if there is more than one file
+    let overall_progress_bar = if downloads.len() > 1 {
+        let progress_bar = progress.add(ProgressBar::new(downloads.len() as u64));
+        progress_bar.set_message(format!("Downloading {} files...", downloads.len()));
+        progress_bar.set_style(
+            ProgressStyle::default_bar()
+                .template("{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.green/white}] {pos}/{len} files")?
+                .progress_chars("#>-"),
+        );
+
+        Some(progress_bar)
+    } else {
+        None
+    };
+
+    let paths = stream::iter(downloads)
+        .map(|(url, message)| {
+            let progress = &progress;
+            let overall_progress_bar = &overall_progress_bar;
+
+            async move {
+                let path =
+                    fetch_cached_with_progress(client, url, message, options, progress).await?;
+
+                if let Some(progress_bar) = overall_progress_bar {
+                    progress_bar.inc(1);
+                }
+
+                Ok::<_, anyhow::Error>((url.to_owned(), path))
+            }
+        })
+        .buffer_unordered(options.jobs.max(1))
+        .try_collect()
+        .await?;
+
+    if let Some(progress_bar) = overall_progress_bar {
+        progress_bar.finish();
+    }
+
+    Ok(paths)
+}
+
+async fn fetch_cached_with_progress(
+    client: &Client,
+    url: &str,
+    message: &str,
+    options: &DownloadOptions,
+    progress: &MultiProgress,
+) -> Result<PathBuf> {
+    let cache_path = get_cache_path();
+    create_dir_all(&cache_path)?;
+
+    let key = get_cache_key(url);
+    let file_path = cache_path.join(&key);
+    let cached_entry = read_cache_entry(&get_entry_path(&file_path))
+        .filter(|entry| entry.url == url && file_path.exists());
+
+    if options.offline {
+        return match cached_entry {
+            Some(_) => Ok(file_path),
+            None => bail!("{} is not available in the download cache", url),
+        };
+    }
+
+    // Partial downloads can only be resumed if it is known which remote file they belong to
+    let part_path = cache_path.join(format!("{}.part", key));
+    let part_entry = read_cache_entry(&get_entry_path(&part_path));
+
+    if part_path.exists()
+        && !part_entry.is_some_and(|entry| entry.url == url && entry.is_validated())
+    {
+        remove_file(&part_path)?;
+    }
+
+    let download = download_file(
+        client,
+        url,
+        &part_path,
+        cached_entry.as_ref(),
+        message,
+        options,
+        progress,
+    )
+    .await?;
+
+    if download == Download::NotModified {
+        progress.suspend(|| println!("{} not modified, using cached copy.", url));
+        return Ok(file_path);
+    }
+
+    rename(get_entry_path(&part_path), get_entry_path(&file_path))?;
+    rename(part_path, &file_path)?;
+
+    Ok(file_path)
+}
+
+fn get_cache_path() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("starch")
+        .join("downloads")
+}
+
+fn get_cache_key(url: &str) -> String {
+    let file_name = url.rsplit('/').next().unwrap_or_default();
+    format!("{:08x}-{}", crc32fast::hash(url.as_bytes()), file_name)
+}
+
+/// Cached files and partial downloads keep the headers they were downloaded with next to them
+fn get_entry_path(file_path: &Path) -> PathBuf {
+    let mut entry_path = file_path.as_os_str().to_owned();
+    entry_path.push(".json");
+    PathBuf::from(entry_path)
+}
+
+fn get_header(headers: &HeaderMap, name: reqwest::header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned)
+}
+
+fn read_cache_entry(path: &Path) -> Option<CacheEntry> {
+    let reader = BufReader::new(File::open(path).ok()?);
+    serde_json::from_reader(reader).ok()
+}
+
+fn write_cache_entry(path: &Path, entry: &CacheEntry) -> Result<()> {
+    let mut entry_file = File::create(path)?;
+    entry_file.write_all(serde_json::to_string_pretty(entry)?.as_bytes())?;
+
+    Ok(())
+}
+
+/// Downloads a file unless the cached copy described by the entry is still current
+async fn download_file(
+    client: &Client,
+    url: &str,
+    path: &Path,
+    cached_entry: Option<&CacheEntry>,
+    message: &str,
+    options: &DownloadOptions,
+    progress: &MultiProgress,
+) -> Result<Download> {
+    // Indicatif setup
+    let progress_bar = progress.add(ProgressBar::new(0));
+    progress_bar.set_message(message.to_owned());
+
+    progress_bar.set_style(ProgressStyle::default_bar()
+        .template("{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")?
+        .progress_chars("#>-"));
+
+    // Retry with exponential backoff, resuming from what was already downloaded
+    let mut attempt = 0;
+
+    let download = loop {
+        match try_download_file(client, url, path, cached_entry, &progress_bar).await {
+            Ok(download) => break download,
+            Err(error) if attempt < options.retries && is_retryable(&error) => {
+                attempt += 1;
+
+                let delay = Duration::from_secs(1 << attempt.min(6));
+                progress_bar.println(format!(
+                    "Download of {} failed ({}), retrying in {}s ({}/{})...",
+                    url,
+                    error,
+                    delay.as_secs(),
+                    attempt,
+                    options.retries
+                ));
+
+                cancellable(tokio::time::sleep(delay)).await?;
+            }
+            Err(error) => return Err(error),
+        }
+    };
+
+    if download == Download::NotModified {
+        progress_bar.finish_and_clear();
+    } else {
+        progress_bar.finish();
+    }
+
+    Ok(download)
+}
+
+async fn try_download_file(
+    client: &Client,
+    url: &str,
+    path: &Path,
+    cached_entry: Option<&CacheEntry>,
+    progress_bar: &ProgressBar,
+) -> Result<Download> {
+    let existing_size = path.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+    let entry_path = get_entry_path(path);
+
+    // Reqwest setup
+    let mut request = client.get(url);
+
+    // Ask the server to only send the file if it changed since the cached copy was downloaded
+    if let Some(entry) = cached_entry {
+        if let Some(etag) = &entry.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+
+        if let Some(last_modified) = &entry.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    // Resume the partial file, the server sends the whole file instead if it changed meanwhile
+    if existing_size > 0 {
+        request = request.header(RANGE, format!("bytes={}-", existing_size));
+
+        if let Some(entry) = read_cache_entry(&entry_path)
+            && let Some(validator) = entry.etag.or(entry.last_modified)
+        {
+            request = request.header(IF_RANGE, validator);
+        }
+    }
+
+    let response = cancellable(request.send()).await??;
+
+    if cached_entry.is_some() && response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(Download::NotModified);
+    }
+
+    // The partial file is already complete if the server can't serve anything past it
+    if existing_size > 0 && response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        return Ok(Download::Complete);
+    }
+
+    let response = response.error_for_status()?;
+    let resumed = response.status() == StatusCode::PARTIAL_CONTENT;
+
+    let (mut file, mut downloaded) = if resumed {
+        (OpenOptions::new().append(true).open(path)?, existing_size)
+    } else {
+        // Remember which remote file the partial download belongs to, so it can be resumed later
+        let entry = CacheEntry {
+            url: url.to_owned(),
+            etag: get_header(response.headers(), ETAG),
+            last_modified: get_header(response.headers(), LAST_MODIFIED),
+        };
+        write_cache_entry(&entry_path, &entry)?;
+
+        (File::create(path)?, 0)
+    };
+
+    progress_bar.set_length(downloaded + response.content_length().unwrap_or(0));
+    progress_bar.set_position(downloaded);
+
+    // Download file, the partial file is kept on cancellation so the download can be resumed
+    let mut stream = response.bytes_stream();
+
+    while let Some(item) = cancellable(stream.next()).await? {
+        let chunk = item?;
+        file.write_all(&chunk)?;
+
+        downloaded += chunk.len() as u64;
+        progress_bar.set_position(downloaded);
+    }
+
+    Ok(Download::Complete)
+}
+
+/// Network failures and server errors are worth retrying, client errors are not
+fn is_retryable(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|error| error.status().is_none_or(|status| status.is_server_error()))
+}
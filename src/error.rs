@@ -0,0 +1,115 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// Everything that can make starch fail, each kind of failure has its own exit code
+#[derive(Debug, Error)]
+pub(crate) enum StarchError {
+    #[error("Steam installation not found")]
+    SteamNotFound,
+
+    #[error("RetroArch is not installed through Steam")]
+    RetroArchNotInstalled,
+
+    #[error("No RetroArch config found at {}", .0.display())]
+    RetroArchConfigNotFound(PathBuf),
+
+    #[error("{key} is not set in the RetroArch config")]
+    ConfigKeyMissing { key: String },
+
+    #[error("Playlist {playlist} not found in {}", .directory.display())]
+    PlaylistNotFound {
+        playlist: String,
+        directory: PathBuf,
+    },
+
+    #[error("{game} not found in playlist {playlist}")]
+    GameNotFound { game: String, playlist: String },
+
+    #[error("Invalid archive {}: {reason}", .path.display())]
+    InvalidArchive { path: PathBuf, reason: String },
+
+    #[error("Network error: {0:#}")]
+    Network(anyhow::Error),
+
+    #[error("{0:#}")]
+    Io(anyhow::Error),
+
+    #[error("{0:#}")]
+    Other(anyhow::Error),
+}
+
+impl StarchError {
+    /// Sorts an error into its category. Errors passed up from libraries are wrapped by what caused them.
+    pub fn categorize(error: anyhow::Error) -> StarchError {
+        let error = match error.downcast::<StarchError>() {
+            Ok(error) => return error,
+            Err(error) => error,
+        };
+
+        if error.chain().any(|cause| cause.is::<reqwest::Error>()) {
+            StarchError::Network(error)
+        } else if error.chain().any(|cause| cause.is::<std::io::Error>()) {
+            StarchError::Io(error)
+        } else {
+            StarchError::Other(error)
+        }
+    }
+
+    /// Process exit code, 2 is left to clap for invalid arguments
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            StarchError::Other(_) => 1,
+            StarchError::SteamNotFound => 3,
+            StarchError::RetroArchNotInstalled | StarchError::RetroArchConfigNotFound(_) => 4,
+            StarchError::ConfigKeyMissing { .. } => 5,
+            StarchError::PlaylistNotFound { .. } => 6,
+            StarchError::GameNotFound { .. } => 7,
+            StarchError::InvalidArchive { .. } => 8,
+            StarchError::Network(_) => 9,
+            StarchError::Io(_) => 10,
+        }
+    }
+
+    /// Suggestion on how to fix the problem
+    pub fn hint(&self) -> Option<String> {
+        let hint = match self {
+            StarchError::SteamNotFound => {
+                "Install Steam or pass the RetroArch directory with --retro-arch-path".to_owned()
+            }
+            StarchError::RetroArchNotInstalled => {
+                "Install RetroArch through Steam or pass its directory with --retro-arch-path"
+                    .to_owned()
+            }
+            StarchError::RetroArchConfigNotFound(_) => {
+                "Start RetroArch once so it writes its config, or check --retro-arch-path"
+                    .to_owned()
+            }
+            StarchError::ConfigKeyMissing { key } => format!(
+                "Set {} in RetroArch under Settings > Directory and save the configuration",
+                key
+            ),
+            StarchError::PlaylistNotFound { .. } => {
+                "Playlists are named after their file in the playlist directory, without .lpl"
+                    .to_owned()
+            }
+            StarchError::GameNotFound { .. } => {
+                "Use the label shown in RetroArch, including tags like (USA)".to_owned()
+            }
+            StarchError::InvalidArchive { .. } => {
+                "Download or export the archive again and make sure it comes from a trusted source"
+                    .to_owned()
+            }
+            StarchError::Network(_) => {
+                "Check your connection, try another --mirror or use --offline with cached downloads"
+                    .to_owned()
+            }
+            StarchError::Io(_) => {
+                "Check that the paths exist and that you are allowed to write to them".to_owned()
+            }
+            StarchError::Other(_) => return None,
+        };
+
+        Some(hint)
+    }
+}
@@ -0,0 +1,63 @@
+use std::fs::read;
+use std::path::Path;
+use std::process::Command;
+
+/// Executable names of RetroArch on the different platforms, relative to the install directory
+const RETRO_ARCH_BINARIES: [&str; 3] = [
+    "retroarch",
+    "retroarch.exe",
+    "RetroArch.app/Contents/MacOS/RetroArch",
+];
+
+/// Markers that precede the version number in RetroArch's version output and binary
+const VERSION_MARKERS: [&str; 2] = ["Frontend for libretro -- v", "RetroArch/"];
+
+/// Detects the version of the installed RetroArch frontend (e.g. "1.19.1")
+pub(crate) fn detect_retro_arch_version(retro_arch_path: &Path) -> Option<String> {
+    let binary = RETRO_ARCH_BINARIES
+        .iter()
+        .map(|binary| retro_arch_path.join(binary))
+        .find(|binary| binary.is_file())?;
+
+    // Look for the version string in the binary first so nothing has to be executed
+    if let Some(version) = read(&binary)
+        .ok()
+        .and_then(|data| find_version(&String::from_utf8_lossy(&data)))
+    {
+        return Some(version);
+    }
+
+    let output = Command::new(&binary).arg("--version").output().ok()?;
+
+    find_version(&String::from_utf8_lossy(&output.stdout))
+        .or_else(|| find_version(&String::from_utf8_lossy(&output.stderr)))
+}
+
+fn find_version(text: &str) -> Option<String> {
+    VERSION_MARKERS.iter().find_map(|marker| {
+        text.match_indices(marker).find_map(|(position, _)| {
+            let version: String = text[position + marker.len()..]
+                .chars()
+                .take_while(|char| char.is_ascii_digit() || *char == '.')
+                .collect();
+            let version = version.trim_end_matches('.');
+
+            // Only accept proper release numbers like 1.19.1
+            let parts: Vec<&str> = version.split('.').collect();
+            (parts.len() == 3 && parts.iter().all(|part| !part.is_empty()))
+                .then(|| version.to_owned())
+        })
+    })
+}
+
+/// Compares release numbers like 1.19.1 part by part
+pub(crate) fn is_older_version(version: &str, other: &str) -> bool {
+    let parse = |version: &str| -> Vec<u32> {
+        version
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+
+    parse(version) < parse(other)
+}
@@ -1,11 +1,13 @@
-use crate::import_export::{Playlist, get_file_name};
+use crate::cancel::check_cancelled;
+use crate::error::StarchError;
+use crate::import_export::{Playlist, get_file_name, get_thumbnail_file_name};
 use crate::{get_path_from_config, get_retro_arch_config};
 
-use std::fs::File;
+use std::fs::{File, remove_file};
 use std::io::{BufReader, Read, Seek, Write};
 use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow};
 use indicatif::{ProgressBar, ProgressStyle};
 use tempfile::tempdir;
 use zip::write::{FileOptions, ZipWriter};
@@ -25,6 +27,14 @@ pub(crate) fn export(
 
     let playlist_file_path = playlist_directory.join(format!("{}.lpl", playlist));
 
+    if !playlist_file_path.exists() {
+        return Err(StarchError::PlaylistNotFound {
+            playlist: playlist.to_owned(),
+            directory: playlist_directory,
+        }
+        .into());
+    }
+
     // Parse playlist
     let playlist_file = File::open(playlist_file_path)?;
     let reader = BufReader::new(playlist_file);
@@ -34,21 +44,25 @@ pub(crate) fn export(
         .items
         .iter()
         .find(|item| item.label == *game)
-        .expect("Specified game not found in playlist");
+        .ok_or_else(|| StarchError::GameNotFound {
+            game: game.to_owned(),
+            playlist: playlist.to_owned(),
+        })?;
 
     // Find thumbnail files
+    let thumbnail_file_name = get_thumbnail_file_name(game);
     let boxart_file_path = thumbnails_directory
         .join(playlist)
         .join("Named_Boxarts")
-        .join(format!("{}.png", game));
+        .join(&thumbnail_file_name);
     let snap_file_path = thumbnails_directory
         .join(playlist)
         .join("Named_Snaps")
-        .join(format!("{}.png", game));
+        .join(&thumbnail_file_name);
     let title_file_path = thumbnails_directory
         .join(playlist)
         .join("Named_Titles")
-        .join(format!("{}.png", game));
+        .join(&thumbnail_file_name);
 
     // Build new playlist
     let mut new_playlist = parsed_playlist.clone();
@@ -62,15 +76,14 @@ pub(crate) fn export(
     let temp_dir = tempdir()?;
     let new_playlist_file_path = temp_dir.path().join(format!("{}.lpl", playlist));
     let mut new_playlist_file = File::create(&new_playlist_file_path)?;
-    new_playlist_file.write_all(
-        serde_json::to_string_pretty(&new_playlist)
-            .unwrap()
-            .as_bytes(),
-    )?;
+    new_playlist_file.write_all(serde_json::to_string_pretty(&new_playlist)?.as_bytes())?;
 
     // Create all files needed for export
     let temp_playlist_file = File::open(new_playlist_file_path)?;
-    let rom_file = File::open(&playlist_item.path)?;
+    let rom_file = File::open(&playlist_item.path)
+        .with_context(|| format!("Failed to open rom {:?}", playlist_item.path))?;
+    let rom_file_name = get_file_name(playlist_item.path.as_str())
+        .ok_or_else(|| anyhow!("Playlist entry {:?} has no rom file name", game))?;
     let boxart_file: File;
     let snap_file: File;
     let title_file: File;
@@ -78,21 +91,17 @@ pub(crate) fn export(
     // Build the zip file and write to disk
     let mut files_and_paths = vec![
         (&temp_playlist_file, format!("playlists/{}.lpl", playlist)),
-        (
-            &rom_file,
-            format!(
-                "roms/{}/{}",
-                playlist,
-                get_file_name(playlist_item.path.as_str()).unwrap()
-            ),
-        ),
+        (&rom_file, format!("roms/{}/{}", playlist, rom_file_name)),
     ];
 
     if Path::new(&boxart_file_path).exists() {
         boxart_file = File::open(boxart_file_path)?;
         files_and_paths.push((
             &boxart_file,
-            format!("thumbnails/{}/Named_Boxarts/{}.png", playlist, game),
+            format!(
+                "thumbnails/{}/Named_Boxarts/{}",
+                playlist, thumbnail_file_name
+            ),
         ));
     }
 
@@ -100,7 +109,10 @@ pub(crate) fn export(
         snap_file = File::open(snap_file_path)?;
         files_and_paths.push((
             &snap_file,
-            format!("thumbnails/{}/Named_Snaps/{}.png", playlist, game),
+            format!(
+                "thumbnails/{}/Named_Snaps/{}",
+                playlist, thumbnail_file_name
+            ),
         ));
     }
 
@@ -108,7 +120,10 @@ pub(crate) fn export(
         title_file = File::open(title_file_path)?;
         files_and_paths.push((
             &title_file,
-            format!("thumbnails/{}/Named_Titles/{}.png", playlist, game),
+            format!(
+                "thumbnails/{}/Named_Titles/{}",
+                playlist, thumbnail_file_name
+            ),
         ));
     }
 
@@ -120,6 +135,18 @@ pub(crate) fn export(
 pub fn write_files_to_zip(files: &[(&File, String)], zip_path: &Path) -> Result<()> {
     let zip_file = File::create(zip_path)
         .with_context(|| format!("Failed to create zip archive at {:?}", zip_path))?;
+
+    // Don't leave a partial archive behind if writing fails or gets cancelled
+    if let Err(error) = write_zip(files, zip_file) {
+        remove_file(zip_path)
+            .with_context(|| format!("Failed to remove partial zip archive {:?}", zip_path))?;
+        return Err(error);
+    }
+
+    Ok(())
+}
+
+fn write_zip(files: &[(&File, String)], zip_file: File) -> Result<()> {
     let mut zip = ZipWriter::new(zip_file);
 
     // Calculate total size for progress bar
@@ -153,6 +180,8 @@ pub fn write_files_to_zip(files: &[(&File, String)], zip_path: &Path) -> Result<
 
         let mut buffer = [0u8; 8192];
         loop {
+            check_cancelled()?;
+
             let bytes_read = file
                 .read(&mut buffer)
                 .with_context(|| format!("Failed to read from file {:?}", target_path))?;
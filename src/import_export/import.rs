@@ -1,12 +1,17 @@
-use crate::import_export::{Playlist, get_file_name, get_file_stem};
+use crate::cancel::check_cancelled;
+use crate::error::StarchError;
+use crate::import_export::{Playlist, get_file_name, get_file_stem, get_thumbnail_file_name};
+use crate::update_cores::get_enclosed_path;
 use crate::{get_path_from_config, get_retro_arch_config};
 
 use std::env::home_dir;
+use std::fmt::Display;
 use std::fs;
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 
+use anyhow::{Context, anyhow};
 use indicatif::{ProgressBar, ProgressStyle};
 use zip::ZipArchive;
 
@@ -15,9 +20,17 @@ pub(crate) fn import(
     destination: Option<PathBuf>,
     retro_arch_path: Option<PathBuf>,
 ) -> anyhow::Result<()> {
+    let invalid_archive = |reason: String| StarchError::InvalidArchive {
+        path: origin.to_owned(),
+        reason,
+    };
+    let damaged = |error: &dyn Display| invalid_archive(format!("it can't be read ({})", error));
+
     // Read in import archive
-    let file = File::open(origin).expect("Could not read import file");
-    let mut archive = ZipArchive::new(BufReader::new(file)).expect("Could not read import file");
+    let file = File::open(origin)
+        .with_context(|| format!("Could not read import file {}", origin.display()))?;
+    let mut archive = ZipArchive::new(BufReader::new(file))
+        .map_err(|error| invalid_archive(format!("not a zip archive ({})", error)))?;
 
     let mut playlist: String = String::new();
     let mut game: String = String::new();
@@ -28,27 +41,86 @@ pub(crate) fn import(
     let mut snap_file_buf: Vec<u8> = Vec::new();
     let mut title_file_buf: Vec<u8> = Vec::new();
 
+    // Import archives may come from others, so reject entries that try to leave their directory
+    let mut offending_entries = Vec::new();
+
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i).map_err(|error| damaged(&error))?;
+
+        if entry.is_symlink() {
+            offending_entries.push(format!("  {} (symbolic link)", entry.name()));
+        } else if get_enclosed_path(entry.name()).is_none() {
+            offending_entries.push(format!("  {} (path leaves the destination)", entry.name()));
+        }
+    }
+
+    if !offending_entries.is_empty() {
+        return Err(invalid_archive(format!(
+            "it contains unsafe entries:\n{}",
+            offending_entries.join("\n")
+        ))
+        .into());
+    }
+
     for i in 0..archive.len() {
-        let mut entry = archive.by_index(i).ok().unwrap();
+        let mut entry = archive.by_index(i).map_err(|error| damaged(&error))?;
         let name = entry.name().to_owned();
 
         // Check if the file is at root level and matches the extension
         if name.starts_with("playlists") {
             let mut buf = Vec::new();
-            entry.read_to_end(&mut buf).ok().unwrap();
-
-            playlist = get_file_stem(name.as_str()).unwrap().to_string();
-
-            parsed_playlist = serde_json::from_slice(&buf)?;
-            game = parsed_playlist.items.first().unwrap().label.to_owned();
+            entry
+                .read_to_end(&mut buf)
+                .map_err(|error| damaged(&error))?;
+
+            playlist = get_file_stem(name.as_str()).unwrap_or_default().to_string();
+
+            parsed_playlist = serde_json::from_slice(&buf).map_err(|error| {
+                invalid_archive(format!("its playlist can't be read ({})", error))
+            })?;
+            game = parsed_playlist
+                .items
+                .first()
+                .ok_or_else(|| invalid_archive("its playlist is empty".to_owned()))?
+                .label
+                .to_owned();
         } else if name.starts_with("roms") {
-            entry.read_to_end(&mut rom_file_buf).ok().unwrap();
+            entry
+                .read_to_end(&mut rom_file_buf)
+                .map_err(|error| damaged(&error))?;
         } else if name.starts_with("thumbnails") && name.contains("Named_Boxarts") {
-            entry.read_to_end(&mut boxart_file_buf).ok().unwrap();
+            entry
+                .read_to_end(&mut boxart_file_buf)
+                .map_err(|error| damaged(&error))?;
         } else if name.starts_with("thumbnails") && name.contains("Named_Snaps") {
-            entry.read_to_end(&mut snap_file_buf).ok().unwrap();
+            entry
+                .read_to_end(&mut snap_file_buf)
+                .map_err(|error| damaged(&error))?;
         } else if name.starts_with("thumbnails") && name.contains("Named_Titles") {
-            entry.read_to_end(&mut title_file_buf).ok().unwrap();
+            entry
+                .read_to_end(&mut title_file_buf)
+                .map_err(|error| damaged(&error))?;
+        }
+    }
+
+    if parsed_playlist.items.is_empty() {
+        return Err(invalid_archive("it does not contain a playlist".to_owned()).into());
+    }
+
+    // Playlist and ROM names end up in file paths and must not point anywhere else, the game name
+    // is only used for thumbnails which get sanitized like RetroArch does it
+    let rom_file_name = parsed_playlist
+        .items
+        .first()
+        .and_then(|item| get_file_name(item.path.as_str()))
+        .unwrap_or_default()
+        .to_owned();
+
+    for (kind, name) in [("playlist", &playlist), ("ROM", &rom_file_name)] {
+        if !is_plain_file_name(name) {
+            return Err(
+                invalid_archive(format!("it contains an unsafe {} name {:?}", kind, name)).into(),
+            );
         }
     }
 
@@ -80,60 +152,65 @@ pub(crate) fn import(
         new_playlist = parsed_playlist.clone();
         new_playlist.items = vec![];
 
-        let destination = destination
-            .or_else(|| Some(home_dir().unwrap().join("Roms")))
-            .unwrap();
+        let destination = match destination {
+            Some(destination) => destination,
+            None => home_dir()
+                .ok_or_else(|| anyhow!("Home directory not found, please specify a destination"))?
+                .join("Roms"),
+        };
 
-        new_playlist.scan_content_dir = destination.join(&playlist).to_str().unwrap().to_owned();
+        new_playlist.scan_content_dir = destination.join(&playlist).to_string_lossy().into_owned();
     }
 
     // Add imported game to playlist and write to disk
-    let mut new_item = parsed_playlist.items.first().unwrap().clone();
-    let rom_file_path = PathBuf::from(new_playlist.scan_content_dir.to_owned())
-        .join(get_file_name(new_item.path.as_str()).unwrap());
+    let mut new_item = parsed_playlist.items[0].clone();
+    let rom_file_path =
+        PathBuf::from(new_playlist.scan_content_dir.to_owned()).join(&rom_file_name);
 
-    new_item.path = rom_file_path.to_str().unwrap().to_owned();
+    new_item.path = rom_file_path.to_string_lossy().into_owned();
     new_playlist.items.push(new_item);
 
-    let mut new_playlist_file = File::create(&playlist_file_path)?;
-    new_playlist_file.write_all(
-        serde_json::to_string_pretty(&new_playlist)
-            .unwrap()
-            .as_bytes(),
-    )?;
-
     // Write game rom (and thumbnails if present) to disk
-    let mut files = vec![(&rom_file_buf, rom_file_path.to_str().unwrap())];
+    let thumbnail_file_name = get_thumbnail_file_name(&game);
+    let mut files = vec![(&rom_file_buf, rom_file_path.as_path())];
 
     let boxart_file_path = thumbnails_directory
         .join(&playlist)
         .join("Named_Boxarts")
-        .join(format!("{}.png", game));
+        .join(&thumbnail_file_name);
     let snap_file_path = thumbnails_directory
         .join(&playlist)
         .join("Named_Snaps")
-        .join(format!("{}.png", game));
+        .join(&thumbnail_file_name);
     let title_file_path = thumbnails_directory
         .join(&playlist)
         .join("Named_Titles")
-        .join(format!("{}.png", game));
+        .join(&thumbnail_file_name);
 
     if !boxart_file_buf.is_empty() {
-        files.push((&boxart_file_buf, boxart_file_path.to_str().unwrap()));
+        files.push((&boxart_file_buf, boxart_file_path.as_path()));
     }
     if !snap_file_buf.is_empty() {
-        files.push((&snap_file_buf, snap_file_path.to_str().unwrap()));
+        files.push((&snap_file_buf, snap_file_path.as_path()));
     }
     if !title_file_buf.is_empty() {
-        files.push((&title_file_buf, title_file_path.to_str().unwrap()));
+        files.push((&title_file_buf, title_file_path.as_path()));
     }
 
     write_files_to_disk(&files)?;
 
+    // The playlist is written last so it never points to a rom that wasn't imported completely
+    let mut new_playlist_file = File::create(&playlist_file_path)?;
+    new_playlist_file.write_all(serde_json::to_string_pretty(&new_playlist)?.as_bytes())?;
+
     Ok(())
 }
 
-fn write_files_to_disk(files: &[(&Vec<u8>, &str)]) -> anyhow::Result<()> {
+fn is_plain_file_name(name: &str) -> bool {
+    get_enclosed_path(name).is_some_and(|path| path.components().count() == 1)
+}
+
+fn write_files_to_disk(files: &[(&Vec<u8>, &Path)]) -> anyhow::Result<()> {
     // Calculate total bytes to write across all files
     let total_bytes: u64 = files.iter().map(|(data, _)| data.len() as u64).sum();
 
@@ -148,26 +225,54 @@ fn write_files_to_disk(files: &[(&Vec<u8>, &str)]) -> anyhow::Result<()> {
 
     let mut total_written = 0u64;
 
-    // Write each file
-    for (data, path) in files.iter() {
-        let dir = Path::new(path)
-            .parent()
-            .ok_or_else(|| anyhow::anyhow!("no parent directory"))?;
-
-        // Create every missing directory in the chain.
-        fs::create_dir_all(dir)?;
-
-        let file = File::create(path)?;
-        let mut writer = BufWriter::new(file);
-
-        // Write this file's data in chunks
-        for chunk in data.chunks(8192) {
-            writer.write_all(chunk)?;
-            total_written += chunk.len() as u64;
-            progress_bar.set_position(total_written);
+    // Files are written next to their destination first and only moved into place once all of
+    // them are complete, so failed or cancelled imports don't leave half-written files behind
+    let part_paths: Vec<PathBuf> = files
+        .iter()
+        .map(|(_, path)| {
+            let mut part_path = path.as_os_str().to_owned();
+            part_path.push(".part");
+            PathBuf::from(part_path)
+        })
+        .collect();
+
+    let written = files
+        .iter()
+        .zip(&part_paths)
+        .try_for_each(|((data, path), part_path)| {
+            let dir = path
+                .parent()
+                .ok_or_else(|| anyhow::anyhow!("no parent directory"))?;
+
+            // Create every missing directory in the chain.
+            fs::create_dir_all(dir)?;
+
+            let file = File::create(part_path)?;
+            let mut writer = BufWriter::new(file);
+
+            // Write this file's data in chunks
+            for chunk in data.chunks(8192) {
+                check_cancelled()?;
+
+                writer.write_all(chunk)?;
+                total_written += chunk.len() as u64;
+                progress_bar.set_position(total_written);
+            }
+
+            writer.flush()?;
+            anyhow::Ok(())
+        });
+
+    if let Err(error) = written {
+        for part_path in part_paths.iter().filter(|part_path| part_path.exists()) {
+            fs::remove_file(part_path)?;
         }
 
-        writer.flush()?;
+        return Err(error);
+    }
+
+    for ((_, path), part_path) in files.iter().zip(&part_paths) {
+        fs::rename(part_path, path)?;
     }
 
     progress_bar.finish();
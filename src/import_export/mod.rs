@@ -44,3 +44,14 @@ fn get_file_name(path_str: &str) -> Option<&str> {
 fn get_file_stem(path: &str) -> Option<&str> {
     Path::new(path).file_stem().and_then(|stem| stem.to_str())
 }
+
+/// RetroArch names thumbnails after the entry label, with characters that can't be part of a file
+/// name replaced by underscores
+fn get_thumbnail_file_name(label: &str) -> String {
+    let name: String = label
+        .chars()
+        .map(|c| if "&*/:`<>?\\|\"".contains(c) { '_' } else { c })
+        .collect();
+
+    format!("{}.png", name)
+}
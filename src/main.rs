@@ -1,11 +1,45 @@
+mod backup;
+mod bios;
+mod buildbot;
+mod cancel;
+mod core_info;
+mod core_report;
+mod cores;
+mod download;
+mod error;
+mod frontend;
 mod import_export;
+mod manifest;
+mod pin_cores;
+mod prune_cores;
+mod rollback_cores;
+mod steam;
+mod target;
+mod update_assets;
 mod update_cores;
 
+use crate::bios::check_bios;
+use crate::cancel::{CANCELLED_EXIT_CODE, Cancelled, listen_for_cancellation};
+use crate::cores::find::find_cores;
+use crate::cores::info::show_core_info;
+use crate::cores::install::install_core;
+use crate::cores::list::list_cores;
+use crate::cores::remove::remove_core;
+use crate::cores::verify::verify_cores;
+use crate::download::DownloadOptions;
+use crate::error::StarchError;
 use crate::import_export::export::export;
 use crate::import_export::import::import;
-use crate::update_cores::update_cores;
+use crate::pin_cores::{pin_core, unpin_core};
+use crate::prune_cores::prune_cores;
+use crate::rollback_cores::rollback_cores;
+use crate::steam::RETRO_ARCH_APP_ID;
+use crate::target::Target;
+use crate::update_assets::update_assets;
+use crate::update_cores::{UpdateCoresOptions, update_cores};
 
 use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
@@ -21,7 +55,7 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    #[command(about = "Updates all cores (even non Steam cores)")]
+    #[command(about = "Updates installed cores (even non Steam cores)")]
     UpdateCores {
         #[arg(
             help = "Version of cores to download",
@@ -30,6 +64,218 @@ enum Commands {
         )]
         version: String,
 
+        #[arg(
+            short,
+            long,
+            help = "Download the complete core bundle instead of only the installed cores"
+        )]
+        all: bool,
+
+        #[arg(
+            long,
+            help = "Only report which files would be added or overwritten without writing anything"
+        )]
+        dry_run: bool,
+
+        #[arg(
+            long,
+            help = "Number of backup generations of replaced cores to keep (0 disables backups)",
+            default_value_t = 5
+        )]
+        keep_backups: usize,
+
+        #[command(flatten)]
+        download_options: DownloadOptions,
+
+        #[arg(
+            short,
+            long,
+            help = "Base URL of a buildbot mirror (Buildbot URLs from the RetroArch config will be used otherwise)"
+        )]
+        mirror: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            help = "Install cores from a local RetroArch_cores.7z, core zip or directory of cores instead of downloading them"
+        )]
+        source: Option<PathBuf>,
+
+        #[arg(
+            long,
+            help = "Also replace cores that were installed through RetroArch's Steam DLCs"
+        )]
+        replace_steam_cores: bool,
+
+        #[arg(
+            long,
+            help = "Download the stable cores matching the installed RetroArch version",
+            conflicts_with = "version"
+        )]
+        match_frontend: bool,
+
+        #[arg(
+            long,
+            help = "Download cores for another operating system (linux, windows or macos)"
+        )]
+        target_os: Option<String>,
+
+        #[arg(
+            long,
+            help = "Download cores for another architecture (x86_64, x86, armhf or aarch64)"
+        )]
+        target_arch: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            help = "Write cores and info files into this directory instead of the RetroArch installation"
+        )]
+        output: Option<PathBuf>,
+
+        #[arg(long, help = "Also write the update report as JSON to this file")]
+        report: Option<PathBuf>,
+
+        #[arg(
+            short,
+            long,
+            help = "Manually override RetroArch path (Will be queried from Steam otherwise)"
+        )]
+        retro_arch_path: Option<PathBuf>,
+    },
+
+    #[command(about = "Updates RetroArch assets, databases, cheats, shaders and overlays")]
+    UpdateAssets {
+        #[arg(
+            help = "Components to update (assets, autoconfig, database-rdb, database-cursors, cheats, overlays, shaders-slang, shaders-glsl, shaders-cg, info). Updates all if unset"
+        )]
+        components: Vec<String>,
+
+        #[arg(
+            long,
+            help = "Only report how many files would be added or overwritten without writing anything"
+        )]
+        dry_run: bool,
+
+        #[command(flatten)]
+        download_options: DownloadOptions,
+
+        #[arg(
+            short,
+            long,
+            help = "Base URL of a buildbot mirror (Buildbot URLs from the RetroArch config will be used otherwise)"
+        )]
+        mirror: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            help = "Manually override RetroArch path (Will be queried from Steam otherwise)"
+        )]
+        retro_arch_path: Option<PathBuf>,
+    },
+
+    #[command(about = "Lists, installs and removes individual cores")]
+    Core {
+        #[command(subcommand)]
+        command: CoreCommands,
+    },
+
+    #[command(about = "Checks BIOS and firmware files required by the installed cores")]
+    Bios {
+        #[command(subcommand)]
+        command: BiosCommands,
+    },
+
+    #[command(
+        about = "Removes cores that are gone from the buildbot, lack an info file or aren't used by any playlist"
+    )]
+    PruneCores {
+        #[arg(
+            help = "Version of cores to compare against",
+            default_value = "nightly",
+            required = false
+        )]
+        version: String,
+
+        #[arg(short, long, help = "Remove all found cores without asking")]
+        yes: bool,
+
+        #[arg(
+            long,
+            help = "Number of backup generations of removed cores to keep (0 disables backups)",
+            default_value_t = 5
+        )]
+        keep_backups: usize,
+
+        #[command(flatten)]
+        download_options: DownloadOptions,
+
+        #[arg(
+            short,
+            long,
+            help = "Base URL of a buildbot mirror (Buildbot URLs from the RetroArch config will be used otherwise)"
+        )]
+        mirror: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            help = "Manually override RetroArch path (Will be queried from Steam otherwise)"
+        )]
+        retro_arch_path: Option<PathBuf>,
+    },
+
+    #[command(about = "Restores cores replaced by a previous update")]
+    RollbackCores {
+        #[arg(
+            help = "Core to roll back (e.g. snes9x). Rolls back the whole update if unset",
+            required = false
+        )]
+        core: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            help = "Backup generation to restore from (Latest will be used if unset)"
+        )]
+        generation: Option<String>,
+
+        #[arg(short, long, help = "List available backup generations")]
+        list: bool,
+
+        #[arg(
+            short,
+            long,
+            help = "Manually override RetroArch path (Will be queried from Steam otherwise)"
+        )]
+        retro_arch_path: Option<PathBuf>,
+    },
+
+    #[command(about = "Pins a core to a release channel or its currently installed build")]
+    PinCore {
+        #[arg(help = "Core to pin (e.g. snes9x)", required = true)]
+        core: String,
+
+        #[arg(
+            help = "Version to pin the core to (e.g. nightly or 1.19.1). Holds the currently installed build if unset",
+            required = false
+        )]
+        version: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            help = "Manually override RetroArch path (Will be queried from Steam otherwise)"
+        )]
+        retro_arch_path: Option<PathBuf>,
+    },
+
+    #[command(about = "Removes the pin from a core so it follows regular updates again")]
+    UnpinCore {
+        #[arg(help = "Core to unpin (e.g. snes9x)", required = true)]
+        core: String,
+
         #[arg(
             short,
             long,
@@ -78,16 +324,357 @@ enum Commands {
     },
 }
 
+impl Commands {
+    /// Commands that clean up after themselves when cancelled, all others keep Ctrl-C's default
+    fn is_cancellable(&self) -> bool {
+        matches!(
+            self,
+            Commands::UpdateCores { .. }
+                | Commands::UpdateAssets { .. }
+                | Commands::Export { .. }
+                | Commands::Import { .. }
+                | Commands::Core {
+                    command: CoreCommands::Install { .. }
+                }
+        )
+    }
+}
+
+#[derive(Subcommand)]
+enum CoreCommands {
+    #[command(about = "Lists installed cores with their display name and version")]
+    List {
+        #[arg(
+            short,
+            long,
+            help = "Manually override RetroArch path (Will be queried from Steam otherwise)"
+        )]
+        retro_arch_path: Option<PathBuf>,
+    },
+
+    #[command(about = "Shows the details of a core from its info file")]
+    Info {
+        #[arg(help = "Core to show (e.g. snes9x)", required = true)]
+        core: String,
+
+        #[arg(
+            short,
+            long,
+            help = "Manually override RetroArch path (Will be queried from Steam otherwise)"
+        )]
+        retro_arch_path: Option<PathBuf>,
+    },
+
+    #[command(about = "Finds the cores that can run a file or file extension")]
+    Find {
+        #[arg(help = "File to find a core for", required = false)]
+        file: Option<PathBuf>,
+
+        #[arg(short, long, help = "File extension to find a core for (e.g. chd)")]
+        ext: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            help = "Search all cores with an info file instead of only the installed ones"
+        )]
+        all: bool,
+
+        #[arg(
+            short,
+            long,
+            help = "Manually override RetroArch path (Will be queried from Steam otherwise)"
+        )]
+        retro_arch_path: Option<PathBuf>,
+    },
+
+    #[command(about = "Installs a single core from the buildbot")]
+    Install {
+        #[arg(help = "Core to install (e.g. snes9x)", required = true)]
+        core: String,
+
+        #[arg(
+            help = "Version of the core to download",
+            default_value = "nightly",
+            required = false
+        )]
+        version: String,
+
+        #[arg(
+            long,
+            help = "Number of backup generations of replaced cores to keep (0 disables backups)",
+            default_value_t = 5
+        )]
+        keep_backups: usize,
+
+        #[command(flatten)]
+        download_options: DownloadOptions,
+
+        #[arg(
+            short,
+            long,
+            help = "Base URL of a buildbot mirror (Buildbot URLs from the RetroArch config will be used otherwise)"
+        )]
+        mirror: Option<String>,
+
+        #[arg(
+            long,
+            help = "Replace the core even if it was installed through RetroArch's Steam DLCs"
+        )]
+        replace_steam_core: bool,
+
+        #[arg(
+            short,
+            long,
+            help = "Manually override RetroArch path (Will be queried from Steam otherwise)"
+        )]
+        retro_arch_path: Option<PathBuf>,
+    },
+
+    #[command(
+        about = "Checks that installed core libraries match this platform and can be loaded by RetroArch"
+    )]
+    Verify {
+        #[arg(help = "Core to verify (e.g. snes9x). Verifies all installed cores if unset")]
+        core: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            help = "Manually override RetroArch path (Will be queried from Steam otherwise)"
+        )]
+        retro_arch_path: Option<PathBuf>,
+    },
+
+    #[command(about = "Removes a single installed core")]
+    Remove {
+        #[arg(help = "Core to remove (e.g. snes9x)", required = true)]
+        core: String,
+
+        #[arg(short, long, help = "Also remove the info file of the core")]
+        info: bool,
+
+        #[arg(
+            short,
+            long,
+            help = "Manually override RetroArch path (Will be queried from Steam otherwise)"
+        )]
+        retro_arch_path: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum BiosCommands {
+    #[command(about = "Reports missing, present and wrong-hash BIOS files per core")]
+    Check {
+        #[arg(
+            help = "Core to check (e.g. swanstation). All installed cores will be checked if unset",
+            required = false
+        )]
+        core: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            help = "Only show missing required files and wrong hashes"
+        )]
+        problems: bool,
+
+        #[arg(
+            short,
+            long,
+            help = "Manually override RetroArch path (Will be queried from Steam otherwise)"
+        )]
+        retro_arch_path: Option<PathBuf>,
+    },
+}
+
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     let cli = Cli::parse();
 
+    if cli.command.as_ref().is_some_and(Commands::is_cancellable) {
+        listen_for_cancellation();
+    }
+
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) if error.is::<Cancelled>() => {
+            eprintln!("{}", error);
+            ExitCode::from(CANCELLED_EXIT_CODE)
+        }
+        Err(error) => {
+            let error = StarchError::categorize(error);
+            eprintln!("Error: {}", error);
+
+            if let Some(hint) = error.hint() {
+                eprintln!("Hint: {}", hint);
+            }
+
+            ExitCode::from(error.exit_code())
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
     match &cli.command {
         Some(Commands::UpdateCores {
+            version,
+            all,
+            dry_run,
+            keep_backups,
+            download_options,
+            mirror,
+            source,
+            replace_steam_cores,
+            match_frontend,
+            target_os,
+            target_arch,
+            output,
+            report,
+            retro_arch_path,
+        }) => {
+            let options = UpdateCoresOptions {
+                version: version.to_owned(),
+                all: *all,
+                dry_run: *dry_run,
+                keep_backups: *keep_backups,
+                mirror: mirror.to_owned(),
+                source: source.to_owned(),
+                replace_steam_cores: *replace_steam_cores,
+                match_frontend: *match_frontend,
+                target: Target::find(target_os.as_deref(), target_arch.as_deref())?,
+                output: output.to_owned(),
+                report: report.to_owned(),
+            };
+
+            update_cores(options, *download_options, retro_arch_path.to_owned()).await?;
+        }
+        Some(Commands::UpdateAssets {
+            components,
+            dry_run,
+            download_options,
+            mirror,
+            retro_arch_path,
+        }) => {
+            update_assets(
+                components,
+                *dry_run,
+                mirror.to_owned(),
+                *download_options,
+                retro_arch_path.to_owned(),
+            )
+            .await?;
+        }
+        Some(Commands::Core { command }) => match command {
+            CoreCommands::List { retro_arch_path } => {
+                list_cores(retro_arch_path.to_owned())?;
+            }
+            CoreCommands::Info {
+                core,
+                retro_arch_path,
+            } => {
+                show_core_info(core, retro_arch_path.to_owned())?;
+            }
+            CoreCommands::Find {
+                file,
+                ext,
+                all,
+                retro_arch_path,
+            } => {
+                find_cores(
+                    file.to_owned(),
+                    ext.to_owned(),
+                    *all,
+                    retro_arch_path.to_owned(),
+                )?;
+            }
+            CoreCommands::Install {
+                core,
+                version,
+                keep_backups,
+                download_options,
+                mirror,
+                replace_steam_core,
+                retro_arch_path,
+            } => {
+                install_core(
+                    core,
+                    version.to_owned(),
+                    mirror.to_owned(),
+                    *replace_steam_core,
+                    *keep_backups,
+                    *download_options,
+                    retro_arch_path.to_owned(),
+                )
+                .await?;
+            }
+            CoreCommands::Verify {
+                core,
+                retro_arch_path,
+            } => {
+                verify_cores(core.as_deref(), retro_arch_path.to_owned())?;
+            }
+            CoreCommands::Remove {
+                core,
+                info,
+                retro_arch_path,
+            } => {
+                remove_core(core, *info, retro_arch_path.to_owned())?;
+            }
+        },
+        Some(Commands::Bios { command }) => match command {
+            BiosCommands::Check {
+                core,
+                problems,
+                retro_arch_path,
+            } => {
+                check_bios(core.to_owned(), *problems, retro_arch_path.to_owned())?;
+            }
+        },
+        Some(Commands::PruneCores {
+            version,
+            yes,
+            keep_backups,
+            download_options,
+            mirror,
+            retro_arch_path,
+        }) => {
+            prune_cores(
+                version.to_owned(),
+                *yes,
+                *keep_backups,
+                mirror.to_owned(),
+                *download_options,
+                retro_arch_path.to_owned(),
+            )
+            .await?;
+        }
+        Some(Commands::RollbackCores {
+            core,
+            generation,
+            list,
+            retro_arch_path,
+        }) => {
+            rollback_cores(
+                core.to_owned(),
+                generation.to_owned(),
+                *list,
+                retro_arch_path.to_owned(),
+            )?;
+        }
+        Some(Commands::PinCore {
+            core,
             version,
             retro_arch_path,
         }) => {
-            update_cores(version.to_owned(), retro_arch_path.to_owned()).await?;
+            pin_core(core, version.to_owned(), retro_arch_path.to_owned())?;
+        }
+        Some(Commands::UnpinCore {
+            core,
+            retro_arch_path,
+        }) => {
+            unpin_core(core, retro_arch_path.to_owned())?;
         }
         Some(Commands::Export {
             playlist,
@@ -111,23 +698,35 @@ async fn main() -> Result<()> {
 }
 
 fn get_retro_arch_config(retro_arch_path: Option<PathBuf>) -> Result<(Ini, PathBuf)> {
-    let retro_arch_path = retro_arch_path
-        .or_else(|| {
-            let steam_dir = SteamDir::locate().expect("Steam not found");
-            let (app, library) = steam_dir.find_app(1118310).expect("RetroArch not found")?;
+    let retro_arch_path = match retro_arch_path {
+        Some(retro_arch_path) => retro_arch_path,
+        None => {
+            let steam_dir = SteamDir::locate().map_err(|_| StarchError::SteamNotFound)?;
+            let (app, library) = steam_dir
+                .find_app(RETRO_ARCH_APP_ID)?
+                .ok_or(StarchError::RetroArchNotInstalled)?;
 
-            Some(library.resolve_app_dir(&app))
-        })
-        .expect("RetroArch not installed in Steam");
+            library.resolve_app_dir(&app)
+        }
+    };
 
     let config_file_path = retro_arch_path.join("retroarch.cfg");
+
+    if !config_file_path.exists() {
+        return Err(StarchError::RetroArchConfigNotFound(config_file_path).into());
+    }
+
     Ok((Ini::load_from_file(config_file_path)?, retro_arch_path))
 }
 
 fn get_path_from_config(config: &Ini, key: &str, retro_arch_path: &Path) -> Result<PathBuf> {
+    // RetroArch writes "default" for directories that were never set
     let path = config
         .get_from(None::<String>, key)
-        .unwrap_or_else(|| panic!("Key {key} not found in RetroArch config"));
+        .filter(|path| !path.is_empty() && *path != "default")
+        .ok_or_else(|| StarchError::ConfigKeyMissing {
+            key: key.to_owned(),
+        })?;
 
     let result = if path.starts_with(":") {
         retro_arch_path.join(path.replace(":/", "./").replace(":", ""))
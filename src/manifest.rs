@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+use std::fs::{File, create_dir_all};
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct Manifest {
+    #[serde(default)]
+    pub cores: BTreeMap<String, ManifestEntry>,
+    #[serde(default)]
+    pub pins: BTreeMap<String, Pin>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ManifestEntry {
+    pub source_url: String,
+    pub channel: String,
+    pub build_date: String,
+    pub crc: u32,
+    pub installed_at: u64,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Pin {
+    pub channel: String,
+    /// Set when the core is held at a specific build instead of following a channel
+    pub crc: Option<u32>,
+}
+
+impl Pin {
+    pub fn is_hold(&self) -> bool {
+        self.crc.is_some()
+    }
+
+    pub fn describe(&self) -> String {
+        match self.crc {
+            Some(crc) => format!("held at {} build {:08x}", self.channel, crc),
+            None => format!("pinned to {}", self.channel),
+        }
+    }
+}
+
+impl Manifest {
+    pub fn path(core_path: &Path) -> PathBuf {
+        core_path.join(".starch").join("manifest.json")
+    }
+
+    pub fn load(core_path: &Path) -> Result<Manifest> {
+        let manifest_path = Manifest::path(core_path);
+
+        if !manifest_path.exists() {
+            return Ok(Manifest::default());
+        }
+
+        let reader = BufReader::new(File::open(manifest_path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    pub fn save(&self, core_path: &Path) -> Result<()> {
+        let manifest_path = Manifest::path(core_path);
+        create_dir_all(manifest_path.parent().unwrap())?;
+
+        let mut manifest_file = File::create(manifest_path)?;
+        manifest_file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+
+        Ok(())
+    }
+
+    pub fn record_install(
+        &mut self,
+        core: &str,
+        source_url: &str,
+        channel: &str,
+        build_date: &str,
+        crc: u32,
+    ) {
+        let installed_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        self.cores.insert(
+            core.to_owned(),
+            ManifestEntry {
+                source_url: source_url.to_owned(),
+                channel: channel.to_owned(),
+                build_date: build_date.to_owned(),
+                crc,
+                installed_at,
+            },
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use crate::manifest::{Manifest, Pin};
+use crate::update_cores::{get_core_file_name, get_file_crc};
+use crate::{get_path_from_config, get_retro_arch_config};
+
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+
+pub(crate) fn pin_core(
+    core: &str,
+    version: Option<String>,
+    retro_arch_path: Option<PathBuf>,
+) -> Result<()> {
+    // Get RetroArch config and load the necessary paths from it
+    let (config, retro_arch_path) = get_retro_arch_config(retro_arch_path)?;
+
+    let core_path = get_path_from_config(&config, "libretro_directory", &retro_arch_path)?;
+
+    let core = get_core_file_name(core);
+    let mut manifest = Manifest::load(&core_path)?;
+
+    // Follow the requested channel or hold the currently installed build
+    let pin = match version {
+        Some(version) => Pin {
+            channel: version,
+            crc: None,
+        },
+        None => {
+            let installed_core_path = core_path.join(&core);
+
+            if !installed_core_path.exists() {
+                bail!("{} is not installed, specify a version to pin it to", core);
+            }
+
+            let channel = manifest
+                .cores
+                .get(&core)
+                .map(|entry| entry.channel.to_owned())
+                .unwrap_or_else(|| "nightly".to_owned());
+
+            Pin {
+                channel,
+                crc: Some(get_file_crc(&installed_core_path)?),
+            }
+        }
+    };
+
+    println!("{} {}.", core, pin.describe());
+
+    manifest.pins.insert(core, pin);
+    manifest.save(&core_path)?;
+
+    Ok(())
+}
+
+pub(crate) fn unpin_core(core: &str, retro_arch_path: Option<PathBuf>) -> Result<()> {
+    // Get RetroArch config and load the necessary paths from it
+    let (config, retro_arch_path) = get_retro_arch_config(retro_arch_path)?;
+
+    let core_path = get_path_from_config(&config, "libretro_directory", &retro_arch_path)?;
+
+    let core = get_core_file_name(core);
+    let mut manifest = Manifest::load(&core_path)?;
+
+    if manifest.pins.remove(&core).is_none() {
+        bail!("{} is not pinned", core);
+    }
+
+    manifest.save(&core_path)?;
+
+    println!("{} unpinned.", core);
+    Ok(())
+}
@@ -0,0 +1,215 @@
+use crate::backup::{Backup, prune_backups};
+use crate::buildbot::{Buildbot, fetch_index};
+use crate::core_info::{CoreInfo, get_info_file_name};
+use crate::download::{DownloadOptions, create_client};
+use crate::manifest::Manifest;
+use crate::steam::get_steam_cores;
+use crate::target::Target;
+use crate::update_cores::get_installed_cores;
+use crate::{get_path_from_config, get_retro_arch_config};
+
+use std::collections::HashSet;
+use std::fs::{File, read_dir, remove_file};
+use std::io::{BufReader, Write, stdin, stdout};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde_json::Value;
+
+pub(crate) async fn prune_cores(
+    version: String,
+    yes: bool,
+    keep_backups: usize,
+    mirror: Option<String>,
+    download_options: DownloadOptions,
+    retro_arch_path: Option<PathBuf>,
+) -> Result<()> {
+    // Get RetroArch config and load the necessary paths from it
+    let (config, retro_arch_path) = get_retro_arch_config(retro_arch_path)?;
+
+    let core_path = get_path_from_config(&config, "libretro_directory", &retro_arch_path)?;
+    let info_path = get_path_from_config(&config, "libretro_info_path", &retro_arch_path)?;
+    let playlist_path = get_path_from_config(&config, "playlist_directory", &retro_arch_path)?;
+
+    let mut manifest = Manifest::load(&core_path)?;
+    let steam_cores = get_steam_cores(&core_path);
+
+    let buildbot = Buildbot::new(Some(&config), mirror.as_deref());
+    let client = create_client(&download_options)?;
+
+    let cores_url = buildbot.cores_url(&version, &Target::find(None, None)?);
+    let index = fetch_index(&client, &cores_url, &download_options).await?;
+
+    let installed_cores = get_installed_cores(&core_path)?;
+    let used_cores = get_playlist_cores(&playlist_path, &info_path, &installed_cores)?;
+
+    // Pinned and Steam managed cores are never pruned
+    let mut candidates = Vec::new();
+
+    for core in installed_cores {
+        if manifest.pins.contains_key(&core) || steam_cores.contains(&core) {
+            continue;
+        }
+
+        let mut reasons = Vec::new();
+
+        if !index.iter().any(|entry| entry.core_name() == core) {
+            reasons.push(format!("not on the {} buildbot", version));
+        }
+
+        if !info_path.join(get_info_file_name(&core)).exists() {
+            reasons.push("no info file".to_owned());
+        }
+
+        if !used_cores.contains(&core) {
+            reasons.push("not used by any playlist".to_owned());
+        }
+
+        if !reasons.is_empty() {
+            candidates.push((core, reasons));
+        }
+    }
+
+    if candidates.is_empty() {
+        println!("No cores to prune.");
+        return Ok(());
+    }
+
+    let mut selected_cores = Vec::new();
+
+    for (core, reasons) in &candidates {
+        let description = format!("{} ({})", core, reasons.join(", "));
+
+        if yes {
+            println!("  {}", description);
+            selected_cores.push(core);
+        } else if confirm(&format!("Remove {}?", description))? {
+            selected_cores.push(core);
+        }
+    }
+
+    if selected_cores.is_empty() {
+        println!("Nothing was removed.");
+        return Ok(());
+    }
+
+    // Keep a copy of every removed core so pruning can be rolled back
+    let backup = if keep_backups > 0 {
+        let mut backup = Backup::create(&core_path, &manifest)?;
+
+        for core in &selected_cores {
+            backup.snapshot_core(&core_path, core)?;
+        }
+
+        Some(backup)
+    } else {
+        None
+    };
+
+    for core in &selected_cores {
+        remove_file(core_path.join(core))?;
+        manifest.cores.remove(*core);
+    }
+
+    manifest.save(&core_path)?;
+
+    if let Some(backup) = backup {
+        prune_backups(&core_path, keep_backups)?;
+        println!(
+            "Removed cores were backed up as generation {}.",
+            backup.generation
+        );
+    }
+
+    println!("{} cores pruned.", selected_cores.len());
+    Ok(())
+}
+
+/// Collects the file names of all cores referenced by RetroArch playlists
+fn get_playlist_cores(
+    playlist_path: &Path,
+    info_path: &Path,
+    installed_cores: &[String],
+) -> Result<HashSet<String>> {
+    let mut cores = HashSet::new();
+    let mut detected_extensions = HashSet::new();
+
+    if !playlist_path.exists() {
+        return Ok(cores);
+    }
+
+    for entry in read_dir(playlist_path)? {
+        let path = entry?.path();
+
+        if path.extension().is_none_or(|extension| extension != "lpl") {
+            continue;
+        }
+
+        // Only JSON playlists are supported, the old line based format is skipped
+        let playlist: Value = match serde_json::from_reader(BufReader::new(File::open(&path)?)) {
+            Ok(playlist) => playlist,
+            Err(_) => {
+                println!(
+                    "Warning: Could not read playlist {}, cores used by it may be pruned.",
+                    path.display()
+                );
+                continue;
+            }
+        };
+
+        let items = playlist["items"].as_array().into_iter().flatten();
+
+        // Scanned playlists leave the core to be detected when a game is started
+        for item in items.clone() {
+            let core_path = item["core_path"].as_str().unwrap_or_default();
+
+            if core_path.is_empty() || core_path == "DETECT" {
+                detected_extensions.extend(get_content_extension(&item["path"]));
+            }
+        }
+
+        let core_paths = items
+            .map(|item| &item["core_path"])
+            .chain([&playlist["default_core_path"]])
+            .filter_map(Value::as_str);
+
+        for core_path in core_paths {
+            if let Some(name) = Path::new(core_path).file_name() {
+                cores.insert(name.to_string_lossy().into_owned());
+            }
+        }
+    }
+
+    // Every core able to run a game of such a playlist could be the one that gets detected
+    for core in installed_cores {
+        if let Some(info) = CoreInfo::load_for_core(info_path, core)
+            && detected_extensions
+                .iter()
+                .any(|extension| info.supports_extension(extension))
+        {
+            cores.insert(core.to_owned());
+        }
+    }
+
+    Ok(cores)
+}
+
+/// Content inside of archives is referenced as "archive.zip#game.sfc"
+fn get_content_extension(path: &Value) -> Option<String> {
+    let path = path.as_str()?;
+    let path = path.rsplit('#').next().unwrap_or(path);
+
+    Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+}
+
+fn confirm(question: &str) -> Result<bool> {
+    print!("{} [y/N] ", question);
+    stdout().flush()?;
+
+    let mut answer = String::new();
+    stdin().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
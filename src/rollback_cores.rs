@@ -0,0 +1,100 @@
+use crate::backup::{Backup, list_backups};
+use crate::core_info::get_info_file_name;
+use crate::manifest::Manifest;
+use crate::update_cores::get_core_file_name;
+use crate::{get_path_from_config, get_retro_arch_config};
+
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+
+pub(crate) fn rollback_cores(
+    core: Option<String>,
+    generation: Option<String>,
+    list: bool,
+    retro_arch_path: Option<PathBuf>,
+) -> Result<()> {
+    // Get RetroArch config and load the necessary paths from it
+    let (config, retro_arch_path) = get_retro_arch_config(retro_arch_path)?;
+
+    let core_path = get_path_from_config(&config, "libretro_directory", &retro_arch_path)?;
+    let info_path = get_path_from_config(&config, "libretro_info_path", &retro_arch_path)?;
+
+    let generations = list_backups(&core_path)?;
+
+    if list {
+        if generations.is_empty() {
+            println!("No backups available.");
+        }
+
+        for generation in &generations {
+            let backup = Backup::open(&core_path, generation)?;
+            let names: Vec<&str> = backup
+                .record()
+                .cores
+                .iter()
+                .map(|file| file.name.as_str())
+                .collect();
+
+            println!("{}: {}", generation, names.join(", "));
+        }
+
+        return Ok(());
+    }
+
+    // Roll back the most recent update unless a generation was requested
+    let Some(generation) = generation.or_else(|| generations.last().cloned()) else {
+        bail!("No backups available to roll back to");
+    };
+
+    let backup = Backup::open(&core_path, &generation)?;
+    let mut manifest = Manifest::load(&core_path)?;
+
+    match core {
+        Some(core) => {
+            let core = get_core_file_name(&core);
+
+            if !backup.restore_core(&core_path, &core)? {
+                bail!("{} is not part of backup generation {}", core, generation);
+            }
+
+            backup.restore_info(&info_path, &get_info_file_name(&core))?;
+            restore_manifest_entry(&mut manifest, &backup, &core);
+            manifest.save(&core_path)?;
+
+            println!("{} rolled back to generation {}.", core, generation);
+            println!("Pin it with `starch pin-core` to keep it from being updated again.");
+        }
+        None => {
+            let record = backup.record().clone();
+
+            for file in &record.cores {
+                backup.restore_core(&core_path, &file.name)?;
+                restore_manifest_entry(&mut manifest, &backup, &file.name);
+            }
+
+            for file in &record.info {
+                backup.restore_info(&info_path, &file.name)?;
+            }
+
+            manifest.save(&core_path)?;
+            backup.remove()?;
+
+            println!(
+                "Rolled back {} cores and {} info files from generation {}.",
+                record.cores.len(),
+                record.info.len(),
+                generation
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn restore_manifest_entry(manifest: &mut Manifest, backup: &Backup, core: &str) {
+    match backup.record().manifest.cores.get(core) {
+        Some(entry) => manifest.cores.insert(core.to_owned(), entry.clone()),
+        None => manifest.cores.remove(core),
+    };
+}
@@ -0,0 +1,132 @@
+use crate::manifest::Manifest;
+
+use std::collections::HashSet;
+use std::env::consts;
+use std::fmt::{Display, Formatter};
+use std::fs::{canonicalize, read, read_dir};
+use std::path::Path;
+
+use steamlocate::SteamDir;
+
+pub(crate) const RETRO_ARCH_APP_ID: u32 = 1118310;
+
+/// Who is responsible for keeping an installed core up to date
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoreOwner {
+    Steam,
+    Starch,
+    Unknown,
+}
+
+impl Display for CoreOwner {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CoreOwner::Steam => write!(f, "steam"),
+            CoreOwner::Starch => write!(f, "starch"),
+            CoreOwner::Unknown => write!(f, "-"),
+        }
+    }
+}
+
+pub(crate) fn get_core_owner(
+    core: &str,
+    steam_cores: &HashSet<String>,
+    manifest: &Manifest,
+) -> CoreOwner {
+    if steam_cores.contains(core) {
+        CoreOwner::Steam
+    } else if manifest.cores.contains_key(core) {
+        CoreOwner::Starch
+    } else {
+        CoreOwner::Unknown
+    }
+}
+
+/// Returns the file names of all cores installed through RetroArch's Steam DLCs into the given
+/// core directory. Installations outside of Steam simply don't have any. If Steam pruned the
+/// manifest of a DLC depot, every core in the directory is assumed to belong to Steam.
+pub(crate) fn get_steam_cores(core_path: &Path) -> HashSet<String> {
+    let mut cores = HashSet::new();
+
+    let Ok(steam_dir) = SteamDir::locate() else {
+        return cores;
+    };
+    let Ok(Some((app, library))) = steam_dir.find_app(RETRO_ARCH_APP_ID) else {
+        return cores;
+    };
+
+    // The DLCs only manage the cores of the Steam copy, not those of other RetroArch installs
+    if !is_inside(core_path, &library.resolve_app_dir(&app)) {
+        return cores;
+    }
+
+    // DLC depots are listed alongside the base game depots in the app manifest
+    let depot_caches = [
+        steam_dir.path().join("depotcache"),
+        library.path().join("steamapps").join("depotcache"),
+    ];
+
+    for (depot_id, depot) in &app.installed_depots {
+        if depot.dlc_app_id.is_none() {
+            continue;
+        }
+
+        let manifest_name = format!("{}_{}.manifest", depot_id, depot.manifest);
+        let manifest_path = depot_caches
+            .iter()
+            .map(|depot_cache| depot_cache.join(&manifest_name))
+            .find(|manifest_path| manifest_path.exists());
+
+        match manifest_path {
+            Some(manifest_path) => cores.extend(get_depot_cores(&manifest_path)),
+            None => {
+                println!(
+                    "Warning: The manifest of Steam depot {} was not found, treating all cores in {} as managed by Steam. Verify the integrity of RetroArch's files in Steam to restore it.",
+                    depot_id,
+                    core_path.display()
+                );
+
+                return get_directory_cores(core_path);
+            }
+        }
+    }
+
+    cores
+}
+
+fn get_directory_cores(core_path: &Path) -> HashSet<String> {
+    let suffix = format!("_libretro.{}", consts::DLL_EXTENSION);
+
+    let Ok(entries) = read_dir(core_path) else {
+        return HashSet::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name.ends_with(&suffix))
+        .collect()
+}
+
+fn is_inside(path: &Path, directory: &Path) -> bool {
+    let path = canonicalize(path).unwrap_or_else(|_| path.to_owned());
+    let directory = canonicalize(directory).unwrap_or_else(|_| directory.to_owned());
+
+    path.starts_with(directory)
+}
+
+/// Depot manifests are binary, but the file names they contain are stored as plain strings
+fn get_depot_cores(manifest_path: &Path) -> Vec<String> {
+    let suffix = format!("_libretro.{}", consts::DLL_EXTENSION);
+
+    let Ok(data) = read(manifest_path) else {
+        return vec![];
+    };
+
+    data.split(|byte| !byte.is_ascii_graphic() && *byte != b' ')
+        .filter_map(|bytes| std::str::from_utf8(bytes).ok())
+        .filter(|name| name.ends_with(&suffix))
+        .filter_map(|name| name.rsplit(['/', '\\']).next())
+        .map(str::to_owned)
+        .collect()
+}
@@ -0,0 +1,93 @@
+use std::env::consts;
+use std::fmt::{Display, Formatter};
+
+use anyhow::{Result, bail};
+
+/// Platform cores are downloaded for and its location on the buildbot
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Target {
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub buildbot_path: &'static str,
+    pub core_extension: &'static str,
+}
+
+/// All platforms with a desktop core directory on the buildbot
+const TARGETS: [Target; 7] = [
+    Target::new("linux", "x86_64", "linux/x86_64", "so"),
+    Target::new("linux", "armhf", "linux/armhf", "so"),
+    Target::new("linux", "aarch64", "linux/aarch64", "so"),
+    Target::new("windows", "x86_64", "windows/x86_64", "dll"),
+    Target::new("windows", "x86", "windows/x86", "dll"),
+    Target::new("macos", "x86_64", "apple/osx/x86_64", "dylib"),
+    Target::new("macos", "aarch64", "apple/osx/arm64", "dylib"),
+];
+
+impl Target {
+    const fn new(
+        os: &'static str,
+        arch: &'static str,
+        buildbot_path: &'static str,
+        core_extension: &'static str,
+    ) -> Target {
+        Target {
+            os,
+            arch,
+            buildbot_path,
+            core_extension,
+        }
+    }
+
+    /// Looks up the given platform, missing parts default to the platform starch runs on
+    pub fn find(os: Option<&str>, arch: Option<&str>) -> Result<Target> {
+        let os = normalize_os(os.unwrap_or(consts::OS));
+        let arch = normalize_arch(arch.unwrap_or(consts::ARCH));
+
+        match TARGETS
+            .iter()
+            .find(|target| target.os == os && target.arch == arch)
+        {
+            Some(target) => Ok(*target),
+            None => {
+                let supported: Vec<String> =
+                    TARGETS.iter().map(|target| target.to_string()).collect();
+
+                bail!(
+                    "Unsupported target {}/{}, supported targets are: {}",
+                    os,
+                    arch,
+                    supported.join(", ")
+                )
+            }
+        }
+    }
+
+    /// Whether this is the platform starch runs on
+    pub fn is_host(&self) -> bool {
+        self.os == normalize_os(consts::OS) && self.arch == normalize_arch(consts::ARCH)
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.os, self.arch)
+    }
+}
+
+fn normalize_os(os: &str) -> &str {
+    match os {
+        "win" | "win32" | "win64" => "windows",
+        "osx" | "apple" | "darwin" => "macos",
+        _ => os,
+    }
+}
+
+fn normalize_arch(arch: &str) -> &str {
+    match arch {
+        "amd64" | "x64" => "x86_64",
+        "i386" | "i686" | "x86_32" => "x86",
+        "arm64" => "aarch64",
+        "arm" | "armv7" | "armv7l" => "armhf",
+        _ => arch,
+    }
+}
@@ -0,0 +1,195 @@
+use crate::buildbot::Buildbot;
+use crate::download::{DownloadOptions, create_client, fetch_all_cached};
+use crate::update_cores::{ChangeStatus, extract_archive, plan_archive_extraction};
+use crate::{get_path_from_config, get_retro_arch_config};
+
+use std::fs::create_dir_all;
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+
+/// An archive in the buildbot's frontend assets and where RetroArch expects its contents
+struct AssetComponent {
+    name: &'static str,
+    file_name: &'static str,
+    config_key: &'static str,
+    sub_directory: Option<&'static str>,
+}
+
+const ASSET_COMPONENTS: [AssetComponent; 10] = [
+    AssetComponent::new("assets", "assets.zip", "assets_directory", None),
+    AssetComponent::new(
+        "autoconfig",
+        "autoconfig.zip",
+        "joypad_autoconfig_dir",
+        None,
+    ),
+    AssetComponent::new(
+        "database-rdb",
+        "database-rdb.zip",
+        "content_database_path",
+        None,
+    ),
+    AssetComponent::new(
+        "database-cursors",
+        "database-cursors.zip",
+        "cursor_directory",
+        None,
+    ),
+    AssetComponent::new("cheats", "cheats.zip", "cheat_database_path", None),
+    AssetComponent::new("overlays", "overlays.zip", "overlay_directory", None),
+    AssetComponent::new(
+        "shaders-slang",
+        "shaders_slang.zip",
+        "video_shader_dir",
+        Some("shaders_slang"),
+    ),
+    AssetComponent::new(
+        "shaders-glsl",
+        "shaders_glsl.zip",
+        "video_shader_dir",
+        Some("shaders_glsl"),
+    ),
+    AssetComponent::new(
+        "shaders-cg",
+        "shaders_cg.zip",
+        "video_shader_dir",
+        Some("shaders_cg"),
+    ),
+    AssetComponent::new("info", "info.zip", "libretro_info_path", None),
+];
+
+impl AssetComponent {
+    const fn new(
+        name: &'static str,
+        file_name: &'static str,
+        config_key: &'static str,
+        sub_directory: Option<&'static str>,
+    ) -> AssetComponent {
+        AssetComponent {
+            name,
+            file_name,
+            config_key,
+            sub_directory,
+        }
+    }
+}
+
+pub(crate) async fn update_assets(
+    components: &[String],
+    dry_run: bool,
+    mirror: Option<String>,
+    download_options: DownloadOptions,
+    retro_arch_path: Option<PathBuf>,
+) -> Result<()> {
+    // Get RetroArch config and load the necessary paths from it
+    let (config, retro_arch_path) = get_retro_arch_config(retro_arch_path)?;
+
+    // Update all components unless specific ones were requested
+    let mut selected = Vec::new();
+
+    for name in components {
+        let Some(component) = ASSET_COMPONENTS
+            .iter()
+            .find(|component| component.name == name)
+        else {
+            let names: Vec<&str> = ASSET_COMPONENTS
+                .iter()
+                .map(|component| component.name)
+                .collect();
+
+            bail!(
+                "Unknown asset component {}, available components are: {}",
+                name,
+                names.join(", ")
+            );
+        };
+
+        selected.push(component);
+    }
+
+    if selected.is_empty() {
+        selected = ASSET_COMPONENTS.iter().collect();
+    }
+
+    let buildbot = Buildbot::new(Some(&config), mirror.as_deref());
+    let client = create_client(&download_options)?;
+
+    let mut destinations = Vec::new();
+
+    for component in selected {
+        // Components without a configured directory can't be placed anywhere
+        if config
+            .get_from(None::<String>, component.config_key)
+            .is_none_or(|path| path.is_empty() || path == "default")
+        {
+            println!(
+                "Skipping {} ({} is not set in RetroArch config).",
+                component.name, component.config_key
+            );
+            continue;
+        }
+
+        let mut destination =
+            get_path_from_config(&config, component.config_key, &retro_arch_path)?;
+
+        if let Some(sub_directory) = component.sub_directory {
+            destination = destination.join(sub_directory);
+        }
+
+        destinations.push((component, destination));
+    }
+
+    let downloads: Vec<(String, String)> = destinations
+        .iter()
+        .map(|(component, _)| {
+            (
+                buildbot.frontend_asset_url(component.file_name),
+                format!("Downloading {}...", component.name),
+            )
+        })
+        .collect();
+
+    let download_file_paths = fetch_all_cached(&client, &downloads, &download_options).await?;
+
+    for (component, destination) in destinations {
+        let download_file_path =
+            &download_file_paths[&buildbot.frontend_asset_url(component.file_name)];
+        let changes = plan_archive_extraction(download_file_path, &destination, None)?;
+        let count = |status| {
+            changes
+                .iter()
+                .filter(|(_, change_status)| *change_status == status)
+                .count()
+        };
+
+        println!(
+            "{}: {} new, {} updated, {} unchanged in {}",
+            component.name,
+            count(ChangeStatus::New),
+            count(ChangeStatus::Updated),
+            count(ChangeStatus::Unchanged),
+            destination.display()
+        );
+
+        if dry_run || count(ChangeStatus::Unchanged) == changes.len() {
+            continue;
+        }
+
+        create_dir_all(&destination)?;
+        extract_archive(
+            download_file_path,
+            &destination,
+            None,
+            &format!("Extracting {}...", component.name),
+        )?;
+    }
+
+    if dry_run {
+        println!("Dry run finished, nothing was written.");
+    } else {
+        println!("Assets successfully updated.");
+    }
+
+    Ok(())
+}
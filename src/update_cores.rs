@@ -1,127 +1,966 @@
+use crate::backup::{Backup, prune_backups};
+use crate::buildbot::{Buildbot, IndexEntry, fetch_index, fetch_latest_stable_version};
+use crate::cancel::{check_cancelled, is_cancelled};
+use crate::core_report::{CoreReport, CoreState};
+use crate::cores::verify::warn_about_broken_cores;
+use crate::download::{DownloadOptions, create_client, fetch_all_cached, fetch_cached};
+use crate::error::StarchError;
+use crate::frontend::{detect_retro_arch_version, is_older_version};
+use crate::manifest::Manifest;
+use crate::steam::{CoreOwner, get_core_owner, get_steam_cores};
+use crate::target::Target;
 use crate::{get_path_from_config, get_retro_arch_config};
 
+use std::collections::{HashMap, HashSet};
 use std::env::consts;
-use std::fs::{File, remove_file};
-use std::io::{Read, Write};
-use std::path::{Path, PathBuf};
+use std::fmt::Display;
+use std::fs::{File, copy, read_dir, rename};
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Component, Path, PathBuf};
 
-use anyhow::Result;
-use futures_util::StreamExt;
+use anyhow::{Result, bail};
+use crc32fast::Hasher;
 use indicatif::{ProgressBar, ProgressStyle};
-use reqwest::Client;
-use sevenz_rust::{Password, SevenZReader};
+use sevenz_rust::{Password, SevenZArchiveEntry, SevenZReader};
+use tempfile::{Builder, TempDir, tempdir};
 use zip::ZipArchive;
 
-pub(crate) async fn update_cores(version: String, retro_arch_path: Option<PathBuf>) -> Result<()> {
-    // Get RetroArch config and load the necessary paths from it
-    let (config, retro_arch_path) = get_retro_arch_config(retro_arch_path)?;
+#[derive(Clone, Debug)]
+pub(crate) struct UpdateCoresOptions {
+    pub version: String,
+    pub all: bool,
+    pub dry_run: bool,
+    pub keep_backups: usize,
+    pub mirror: Option<String>,
+    pub source: Option<PathBuf>,
+    pub replace_steam_cores: bool,
+    pub match_frontend: bool,
+    pub target: Target,
+    pub output: Option<PathBuf>,
+    pub report: Option<PathBuf>,
+}
+
+pub(crate) async fn update_cores(
+    options: UpdateCoresOptions,
+    download_options: DownloadOptions,
+    retro_arch_path: Option<PathBuf>,
+) -> Result<()> {
+    let UpdateCoresOptions {
+        version,
+        all,
+        dry_run,
+        keep_backups,
+        mirror,
+        source,
+        replace_steam_cores,
+        match_frontend,
+        target,
+        output,
+        report,
+    } = options.clone();
 
-    let core_path = get_path_from_config(&config, "libretro_directory", &retro_arch_path)?;
-    let info_path = get_path_from_config(&config, "libretro_info_path", &retro_arch_path)?;
+    if output.is_none() && !target.is_host() {
+        bail!(
+            "Cores for {} can't be installed into the local RetroArch, specify an --output directory",
+            target
+        );
+    }
 
-    // Build download URL for RetroArch cores and download and extract them
-    let release_type = if version != "nightly" {
-        format!("stable/{}", version)
+    // Staged core sets only use the RetroArch config for the buildbot URLs, if one was given
+    let (config, retro_arch_path, core_path, info_path) = if let Some(output) = &output {
+        let (config, retro_arch_path) = retro_arch_path
+            .map(|retro_arch_path| get_retro_arch_config(Some(retro_arch_path)))
+            .transpose()?
+            .unzip();
+
+        (
+            config,
+            retro_arch_path,
+            output.join("cores"),
+            output.join("info"),
+        )
     } else {
-        version
+        let (config, retro_arch_path) = get_retro_arch_config(retro_arch_path)?;
+        let core_path = get_path_from_config(&config, "libretro_directory", &retro_arch_path)?;
+        let info_path = get_path_from_config(&config, "libretro_info_path", &retro_arch_path)?;
+
+        (Some(config), Some(retro_arch_path), core_path, info_path)
     };
 
-    let core_download_url = format!(
-        "http://buildbot.libretro.com/{}/{}/{}/RetroArch_cores.7z",
-        release_type,
-        consts::OS,
-        consts::ARCH
-    );
-    let core_download_file_path = core_path.join("cores.7z");
+    let mut manifest = Manifest::load(&core_path)?;
 
-    download_file(
-        &Client::new(),
-        &core_download_url,
-        &core_download_file_path,
-        "Downloading cores...",
-    )
-    .await?;
+    // Cores installed through Steam DLCs are left to Steam unless explicitly requested
+    let steam_cores = if output.is_none() {
+        get_steam_cores(&core_path)
+    } else {
+        HashSet::new()
+    };
+    let protected_cores = if replace_steam_cores {
+        HashSet::new()
+    } else {
+        steam_cores.clone()
+    };
 
-    extract_7zip_file(&core_download_file_path, &core_path, "Extracting cores...")?;
+    if output.is_none() {
+        print_core_owners(&core_path, &steam_cores, &manifest)?;
+    } else {
+        println!("Staging cores for {} in {}.", target, core_path.display());
+    }
 
-    remove_file(core_download_file_path)?;
+    // Install from a local archive or directory instead of the buildbot
+    if let Some(source) = source {
+        return update_cores_from_source(
+            &source,
+            &options,
+            &core_path,
+            &info_path,
+            &mut manifest,
+            &protected_cores,
+        );
+    }
 
-    // Build download URL for RetroArch info files and download and extract them
-    let info_download_url = "https://buildbot.libretro.com/assets/frontend/info.zip";
-    let info_download_file_path = info_path.join("info.zip");
+    let buildbot = Buildbot::new(config.as_ref(), mirror.as_deref());
+    let client = create_client(&download_options)?;
 
-    download_file(
-        &Client::new(),
-        info_download_url,
-        &info_download_file_path,
-        "Downloading info files...",
-    )
-    .await?;
+    // Stable cores should match the installed frontend, nightly cores may need a newer one
+    let frontend_version = retro_arch_path
+        .as_deref()
+        .filter(|_| output.is_none())
+        .and_then(detect_retro_arch_version);
+
+    let version = match (&frontend_version, match_frontend) {
+        (Some(frontend_version), true) => {
+            println!("Using stable cores for RetroArch v{}.", frontend_version);
+            frontend_version.to_owned()
+        }
+        (None, true) => bail!(
+            "Could not detect the installed RetroArch version, specify the version of cores to download instead"
+        ),
+        (Some(frontend_version), false) if version == "nightly" => {
+            // Not knowing the latest release is no reason to fail the update
+            let latest_version = fetch_latest_stable_version(&client, &buildbot, &download_options)
+                .await
+                .ok()
+                .flatten();
 
-    extract_zip_file(
-        &info_download_file_path,
-        &info_path,
-        "Extracting info files...",
+            if let Some(latest_version) = latest_version
+                && is_older_version(frontend_version, &latest_version)
+            {
+                println!(
+                    "Warning: Installing nightly cores for RetroArch v{}, which is older than the latest release v{}. Nightly cores may require a newer frontend, update RetroArch or use --match-frontend to install the matching stable cores.",
+                    frontend_version, latest_version
+                );
+            }
+
+            version
+        }
+        (Some(frontend_version), false) if version != *frontend_version => {
+            println!(
+                "Warning: Installing {} cores for RetroArch v{}.",
+                version, frontend_version
+            );
+            version
+        }
+        _ => version,
+    };
+
+    // Compare the buildbot index against the installed cores to find out what changed
+    let installed_cores = list_core_files(&core_path, target.core_extension)?;
+    let full_bundle = all || installed_cores.is_empty();
+
+    let cores_url = buildbot.cores_url(&version, &target);
+    let index = fetch_index(&client, &cores_url, &download_options).await?;
+
+    let mut plan = plan_core_updates(
+        &index,
+        &cores_url,
+        &version,
+        &installed_cores,
+        &core_path,
+        full_bundle,
+        &manifest,
     )?;
 
-    remove_file(info_download_file_path)?;
+    // Cores pinned to a different channel are planned against that channel's index
+    let mut pinned_channels: Vec<&String> = manifest
+        .pins
+        .values()
+        .filter(|pin| !pin.is_hold() && pin.channel != version)
+        .map(|pin| &pin.channel)
+        .collect();
+    pinned_channels.sort();
+    pinned_channels.dedup();
+
+    for channel in pinned_channels {
+        let pinned_cores_url = buildbot.cores_url(channel, &target);
+        let pinned_index = fetch_index(&client, &pinned_cores_url, &download_options).await?;
+
+        let pinned_plan = plan_core_updates(
+            &pinned_index,
+            &pinned_cores_url,
+            channel,
+            &installed_cores,
+            &core_path,
+            true,
+            &manifest,
+        )?;
+
+        plan.extend(pinned_plan.into_iter().filter(|core| {
+            manifest
+                .pins
+                .get(&core.name)
+                .is_some_and(|pin| pin.channel == *channel)
+        }));
+    }
+
+    plan.retain(|core| {
+        let protected = protected_cores.contains(&core.name);
+
+        if protected {
+            println!(
+                "Skipping {} (managed by Steam, use --replace-steam-cores to replace it).",
+                core.name
+            );
+        }
+
+        !protected
+    });
+
+    print_core_plan(&plan);
+
+    for (core, pin) in &manifest.pins {
+        if pin.is_hold() {
+            println!("Skipping {} ({}).", core, pin.describe());
+        }
+    }
+
+    let changed_cores: Vec<&CorePlan> = plan
+        .iter()
+        .filter(|core| core.status != ChangeStatus::Unchanged)
+        .collect();
+
+    // The complete bundle is only used for cores following the requested channel
+    let (bundle_cores, single_cores): (Vec<&CorePlan>, Vec<&CorePlan>) = changed_cores
+        .iter()
+        .partition(|core| full_bundle && core.channel == version);
+
+    let bundle_download_url = (!bundle_cores.is_empty()).then(|| {
+        format!(
+            "{}/RetroArch_cores.7z",
+            buildbot.cores_url(&version, &target)
+        )
+    });
+
+    let info_download_url = buildbot.frontend_asset_url("info.zip");
+
+    // Only report what would change without touching the RetroArch directories
+    if dry_run {
+        let info_download_file_path = fetch_cached(
+            &client,
+            &info_download_url,
+            "Downloading info files...",
+            &download_options,
+        )
+        .await?;
+
+        let core_changes: Vec<(PathBuf, ChangeStatus)> = plan
+            .iter()
+            .map(|core| (core_path.join(&core.name), core.status))
+            .collect();
+        let info_changes = plan_zip_extraction(&info_download_file_path, &info_path)?;
+
+        println!("Downloads:");
+        for url in bundle_download_url
+            .iter()
+            .chain(single_cores.iter().map(|core| &core.url))
+            .map(String::as_str)
+            .chain([info_download_url.as_str()])
+        {
+            println!("  {}", url);
+        }
+
+        print_file_changes("Cores", &core_changes);
+        print_file_changes("Info files", &info_changes);
+
+        println!("Dry run finished, nothing was written.");
+        return Ok(());
+    }
+
+    if changed_cores.is_empty() {
+        println!("All cores are up to date.");
+    }
+
+    // Archives are downloaded into the download cache and never into the RetroArch directories
+    let mut downloads: Vec<(String, String)> = bundle_download_url
+        .iter()
+        .map(|url| (url.to_owned(), "Downloading cores...".to_owned()))
+        .collect();
+    downloads.extend(
+        single_cores
+            .iter()
+            .map(|core| (core.url.to_owned(), format!("Downloading {}...", core.name))),
+    );
+    downloads.push((
+        info_download_url.to_owned(),
+        "Downloading info files...".to_owned(),
+    ));
+
+    let download_file_paths = fetch_all_cached(&client, &downloads, &download_options).await?;
+
+    // Extract RetroArch info files only if any of them changed
+    let info_download_file_path = &download_file_paths[&info_download_url];
+
+    let info_changes: Vec<PathBuf> = plan_zip_extraction(info_download_file_path, &info_path)?
+        .into_iter()
+        .filter(|(_, status)| *status != ChangeStatus::Unchanged)
+        .map(|(path, _)| path)
+        .collect();
+
+    let removed_upstream: Vec<String> = installed_cores
+        .iter()
+        .filter(|core| {
+            !manifest.pins.contains_key(*core)
+                && !index.iter().any(|entry| entry.core_name() == *core)
+        })
+        .cloned()
+        .collect();
+    let statuses: Vec<(&str, ChangeStatus)> = plan
+        .iter()
+        .map(|core| (core.name.as_str(), core.status))
+        .collect();
+
+    let core_install = CoreInstall {
+        channel: &version,
+        core_path: &core_path,
+        info_path: &info_path,
+        target,
+        keep_backups,
+        report: report.as_deref(),
+    };
+
+    core_install.run(
+        &mut manifest,
+        &statuses,
+        &info_changes,
+        removed_upstream,
+        |manifest| {
+            if let Some(bundle_download_url) = &bundle_download_url {
+                let core_download_file_path = &download_file_paths[bundle_download_url];
+
+                // Held cores, cores pinned to another channel and Steam managed cores must not be
+                // overwritten by the bundle
+                let skipped_cores: Vec<String> = manifest
+                    .pins
+                    .iter()
+                    .filter(|(_, pin)| pin.is_hold() || pin.channel != version)
+                    .map(|(core, _)| core)
+                    .chain(&protected_cores)
+                    .cloned()
+                    .collect();
+
+                let extracted_cores = extract_7zip_file(
+                    core_download_file_path,
+                    &core_path,
+                    "Extracting cores...",
+                    &ExtractMode::Flatten,
+                    &skipped_cores,
+                )?;
+
+                // Cores missing from the bundle keep the build they had before
+                for core in bundle_cores
+                    .iter()
+                    .filter(|core| extracted_cores.contains(&PathBuf::from(&core.name)))
+                {
+                    manifest.record_install(
+                        &core.name,
+                        bundle_download_url,
+                        &core.channel,
+                        &core.date,
+                        core.crc,
+                    );
+                }
+
+                manifest.save(&core_path)?;
+            }
+
+            for core in &single_cores {
+                extract_zip_file(
+                    &download_file_paths[&core.url],
+                    &core_path,
+                    &format!("Extracting {}...", core.name),
+                )?;
+
+                manifest.record_install(&core.name, &core.url, &core.channel, &core.date, core.crc);
+                manifest.save(&core_path)?;
+            }
+
+            if info_changes.is_empty() {
+                println!("Info files are up to date.");
+            } else {
+                extract_zip_file(
+                    info_download_file_path,
+                    &info_path,
+                    "Extracting info files...",
+                )?;
+            }
+
+            Ok(())
+        },
+    )?;
 
     println!("Cores successfully updated.");
     Ok(())
 }
 
-async fn download_file(
-    client: &Client,
-    url: &str,
-    path: &PathBuf,
-    message: &'static str,
+fn update_cores_from_source(
+    source: &Path,
+    options: &UpdateCoresOptions,
+    core_path: &Path,
+    info_path: &Path,
+    manifest: &mut Manifest,
+    protected_cores: &HashSet<String>,
 ) -> Result<()> {
-    // Reqwest setup
-    let response = client.get(url).send().await?;
-    let total_size = response.content_length().unwrap_or(0);
+    let UpdateCoresOptions {
+        dry_run,
+        keep_backups,
+        target,
+        ref report,
+        ..
+    } = *options;
 
-    // Indicatif setup
-    let progress_bar = ProgressBar::new(total_size);
-    progress_bar.set_message(message);
+    // Archives are unpacked into a temporary directory first so all sources can be handled alike
+    let unpacked_dir = tempdir()?;
 
-    progress_bar.set_style(ProgressStyle::default_bar()
-        .template("{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")?
-        .progress_chars("#>-"));
+    let source_dir = if source.is_dir() {
+        source.to_path_buf()
+    } else {
+        match source.extension().and_then(|extension| extension.to_str()) {
+            Some("7z") => {
+                extract_7zip_file(
+                    &source.to_path_buf(),
+                    unpacked_dir.path(),
+                    "Unpacking cores...",
+                    &ExtractMode::Flatten,
+                    &[],
+                )?;
+            }
+            Some("zip") => extract_zip_file(
+                &source.to_path_buf(),
+                unpacked_dir.path(),
+                "Unpacking cores...",
+            )?,
+            _ => bail!(
+                "Unsupported source {}, expected a .7z or .zip archive or a directory",
+                source.display()
+            ),
+        }
 
-    // Download file
-    let mut file = File::create(path)?;
-    let mut downloaded: u64 = 0;
-    let mut stream = response.bytes_stream();
+        unpacked_dir.path().to_path_buf()
+    };
 
-    while let Some(item) = stream.next().await {
-        let chunk = item?;
-        file.write_all(&chunk)?;
+    let source_cores = list_core_files(&source_dir, target.core_extension)?;
 
-        downloaded += chunk.len() as u64;
-        progress_bar.set_position(downloaded);
+    if source_cores.is_empty() {
+        bail!("No cores found in {}", source.display());
     }
 
-    progress_bar.finish();
+    let mut core_changes = Vec::new();
+
+    for core in source_cores {
+        if let Some(pin) = manifest.pins.get(&core) {
+            println!("Skipping {} ({}).", core, pin.describe());
+            continue;
+        }
+
+        if protected_cores.contains(&core) {
+            println!(
+                "Skipping {} (managed by Steam, use --replace-steam-cores to replace it).",
+                core
+            );
+            continue;
+        }
+
+        let installed_core_path = core_path.join(&core);
+        let crc = get_file_crc(&source_dir.join(&core))?;
+
+        let status = if !installed_core_path.exists() {
+            ChangeStatus::New
+        } else if get_file_crc(&installed_core_path)? == crc {
+            ChangeStatus::Unchanged
+        } else {
+            ChangeStatus::Updated
+        };
+
+        core_changes.push((core, crc, status));
+    }
+
+    // Info files can only be updated if the source directory ships them
+    let info_download_file_path = source_dir.join("info.zip");
+    let info_changes = if info_download_file_path.exists() {
+        plan_zip_extraction(&info_download_file_path, info_path)?
+    } else {
+        vec![]
+    };
+
+    if dry_run {
+        let changes: Vec<(PathBuf, ChangeStatus)> = core_changes
+            .iter()
+            .map(|(core, _, status)| (core_path.join(core), *status))
+            .collect();
+
+        print_file_changes("Cores", &changes);
+        print_file_changes("Info files", &info_changes);
+
+        println!("Dry run finished, nothing was written.");
+        return Ok(());
+    }
+
+    let changed_cores: Vec<&(String, u32, ChangeStatus)> = core_changes
+        .iter()
+        .filter(|(_, _, status)| *status != ChangeStatus::Unchanged)
+        .collect();
+    let changed_info: Vec<PathBuf> = info_changes
+        .iter()
+        .filter(|(_, status)| *status != ChangeStatus::Unchanged)
+        .map(|(path, _)| path.to_owned())
+        .collect();
+
+    // Nothing to install still runs through the install below, so the report gets written
+    if changed_cores.is_empty() && changed_info.is_empty() {
+        println!("All cores are up to date.");
+    }
+
+    let statuses: Vec<(&str, ChangeStatus)> = core_changes
+        .iter()
+        .map(|(core, _, status)| (core.as_str(), *status))
+        .collect();
+
+    let core_install = CoreInstall {
+        channel: "local",
+        core_path,
+        info_path,
+        target,
+        keep_backups,
+        report: report.as_deref(),
+    };
+
+    core_install.run(manifest, &statuses, &changed_info, vec![], |manifest| {
+        // Copy the changed cores next to the destination first and move them into place afterwards
+        let staging = create_staging_dir(core_path)?;
+
+        for (core, _, _) in &changed_cores {
+            check_cancelled()?;
+            copy(source_dir.join(core), staging.path().join(core))?;
+        }
+
+        check_cancelled()?;
+        move_staged_files(staging.path(), core_path)?;
+
+        let source_url = source.to_string_lossy();
+
+        for (core, crc, _) in &changed_cores {
+            manifest.record_install(core, &source_url, "local", "", *crc);
+        }
+
+        manifest.save(core_path)?;
+
+        if !changed_info.is_empty() {
+            extract_zip_file(
+                &info_download_file_path,
+                info_path,
+                "Extracting info files...",
+            )?;
+        } else if !info_download_file_path.exists() {
+            println!("No info.zip found in source, info files were not updated.");
+        }
+
+        Ok(())
+    })?;
+
+    println!(
+        "{} cores successfully installed from {}.",
+        changed_cores.len(),
+        source.display()
+    );
     Ok(())
 }
 
-fn extract_zip_file(file: &PathBuf, destination: &Path, message: &'static str) -> Result<()> {
+/// Where and how cores get installed, shared by all ways of installing cores
+pub(crate) struct CoreInstall<'a> {
+    pub channel: &'a str,
+    pub core_path: &'a Path,
+    pub info_path: &'a Path,
+    pub target: Target,
+    pub keep_backups: usize,
+    pub report: Option<&'a Path>,
+}
+
+impl CoreInstall<'_> {
+    /// Replaces cores and info files through `install`. Every file about to change is backed up
+    /// first and put back if installing fails or gets cancelled halfway. Afterwards the installed
+    /// cores are checked and a report of the changes is printed.
+    pub fn run(
+        &self,
+        manifest: &mut Manifest,
+        statuses: &[(&str, ChangeStatus)],
+        info_files: &[PathBuf],
+        removed_upstream: Vec<String>,
+        install: impl FnOnce(&mut Manifest) -> Result<()>,
+    ) -> Result<()> {
+        let changed_cores: Vec<&str> = statuses
+            .iter()
+            .filter(|(_, status)| *status != ChangeStatus::Unchanged)
+            .map(|(core, _)| *core)
+            .collect();
+
+        // Remember versions and sizes of the cores about to change for the update report
+        let cores_before: HashMap<String, CoreState> = changed_cores
+            .iter()
+            .map(|core| {
+                (
+                    core.to_string(),
+                    CoreState::read(self.core_path, self.info_path, core),
+                )
+            })
+            .collect();
+
+        // Keep a copy of every file that gets replaced so the install can be restored if it fails,
+        // it is only kept as a generation for rollbacks if backups are enabled
+        let backup = if !changed_cores.is_empty() || !info_files.is_empty() {
+            let mut backup = Backup::create(self.core_path, manifest)?;
+
+            for core in &changed_cores {
+                backup.snapshot_core(self.core_path, core)?;
+            }
+
+            for path in info_files {
+                let name = path.strip_prefix(self.info_path)?.to_string_lossy();
+                backup.snapshot_info(self.info_path, &name)?;
+            }
+
+            Some(backup)
+        } else {
+            None
+        };
+
+        if let Err(error) = install(manifest) {
+            if let Some(backup) = backup {
+                println!("Restoring the files replaced so far...");
+                backup.restore_all(self.core_path, self.info_path)?;
+            }
+
+            return Err(error);
+        }
+
+        if let Some(backup) = backup {
+            if self.keep_backups == 0 {
+                backup.remove()?;
+            } else {
+                prune_backups(self.core_path, self.keep_backups)?;
+                println!(
+                    "Replaced files were backed up as generation {}.",
+                    backup.generation
+                );
+            }
+        }
+
+        warn_about_broken_cores(self.core_path, &changed_cores, &self.target);
+
+        let core_report = CoreReport::create(
+            self.channel,
+            statuses,
+            &cores_before,
+            removed_upstream,
+            self.core_path,
+            self.info_path,
+        );
+        core_report.print();
+
+        if let Some(report) = self.report {
+            core_report.save(report)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ChangeStatus {
+    Unchanged,
+    Updated,
+    New,
+}
+
+#[derive(Clone, Debug)]
+struct CorePlan {
+    name: String,
+    channel: String,
+    url: String,
+    date: String,
+    crc: u32,
+    status: ChangeStatus,
+}
+
+fn plan_core_updates(
+    index: &[IndexEntry],
+    cores_url: &str,
+    channel: &str,
+    installed_cores: &[String],
+    core_path: &Path,
+    include_new: bool,
+    manifest: &Manifest,
+) -> Result<Vec<CorePlan>> {
+    let mut plan = Vec::new();
+
+    for entry in index {
+        let name = entry.core_name();
+        let installed = installed_cores.iter().any(|core| core == name);
+
+        // Held cores are never touched and cores pinned elsewhere are planned separately
+        if manifest
+            .pins
+            .get(name)
+            .is_some_and(|pin| pin.is_hold() || pin.channel != channel)
+        {
+            continue;
+        }
+
+        let status = if installed {
+            if get_file_crc(&core_path.join(name))? == entry.crc {
+                ChangeStatus::Unchanged
+            } else {
+                ChangeStatus::Updated
+            }
+        } else if include_new {
+            ChangeStatus::New
+        } else {
+            continue;
+        };
+
+        plan.push(CorePlan {
+            name: name.to_owned(),
+            channel: channel.to_owned(),
+            url: format!("{}/latest/{}", cores_url, entry.file_name),
+            date: entry.date.to_owned(),
+            crc: entry.crc,
+            status,
+        });
+    }
+
+    // Installed cores that are not part of the index can't be updated
+    for core in installed_cores {
+        if !manifest.pins.contains_key(core) && !index.iter().any(|entry| entry.core_name() == core)
+        {
+            println!(
+                "{} is not available on the {} buildbot and will be skipped.",
+                core, channel
+            );
+        }
+    }
+
+    Ok(plan)
+}
+
+fn print_core_owners(
+    core_path: &Path,
+    steam_cores: &HashSet<String>,
+    manifest: &Manifest,
+) -> Result<()> {
+    let owners: Vec<CoreOwner> = get_installed_cores(core_path)?
+        .iter()
+        .map(|core| get_core_owner(core, steam_cores, manifest))
+        .collect();
+    let count = |owner| {
+        owners
+            .iter()
+            .filter(|core_owner| **core_owner == owner)
+            .count()
+    };
+
+    println!(
+        "Installed cores: {} managed by Steam, {} managed by starch, {} unmanaged",
+        count(CoreOwner::Steam),
+        count(CoreOwner::Starch),
+        count(CoreOwner::Unknown)
+    );
+
+    Ok(())
+}
+
+fn print_core_plan(plan: &[CorePlan]) {
+    let count = |status| plan.iter().filter(|core| core.status == status).count();
+
+    for core in plan {
+        match core.status {
+            ChangeStatus::Updated => println!("  updated: {} ({})", core.name, core.date),
+            ChangeStatus::New => println!("  new:     {} ({})", core.name, core.date),
+            ChangeStatus::Unchanged => {}
+        }
+    }
+
+    println!(
+        "{} unchanged, {} updated, {} new",
+        count(ChangeStatus::Unchanged),
+        count(ChangeStatus::Updated),
+        count(ChangeStatus::New)
+    );
+}
+
+pub(crate) fn plan_zip_extraction(
+    file: &PathBuf,
+    destination: &Path,
+) -> Result<Vec<(PathBuf, ChangeStatus)>> {
+    plan_zip_extraction_with_mode(
+        file,
+        destination,
+        &ExtractMode::Preserve { strip_prefix: None },
+    )
+}
+
+fn plan_zip_extraction_with_mode(
+    file: &PathBuf,
+    destination: &Path,
+    mode: &ExtractMode,
+) -> Result<Vec<(PathBuf, ChangeStatus)>> {
+    let zip_file = File::open(file)?;
+    let mut archive = ZipArchive::new(zip_file).map_err(|error| invalid_archive(file, error))?;
+    let entry_paths = resolve_zip_entry_paths(&mut archive, file, destination, mode)?;
+    let mut changes = Vec::new();
+
+    for index in 0..archive.len() {
+        let file = archive
+            .by_index(index)
+            .map_err(|error| invalid_archive(file, error))?;
+
+        // Directories and entries outside of the stripped prefix have no path
+        let Some(entry_path) = entry_paths.get(file.name()) else {
+            continue;
+        };
+
+        let path = destination.join(entry_path);
+
+        let status = if !path.exists() {
+            ChangeStatus::New
+        } else if get_file_crc(&path)? == file.crc32() {
+            ChangeStatus::Unchanged
+        } else {
+            ChangeStatus::Updated
+        };
+
+        changes.push((path, status));
+    }
+
+    Ok(changes)
+}
+
+fn print_file_changes(label: &str, changes: &[(PathBuf, ChangeStatus)]) {
+    println!("{}:", label);
+
+    for (path, status) in changes {
+        let action = match status {
+            ChangeStatus::New => "add",
+            ChangeStatus::Updated => "overwrite",
+            ChangeStatus::Unchanged => "unchanged",
+        };
+
+        println!("  {:<9} {}", action, path.display());
+    }
+}
+
+pub(crate) fn get_file_crc(path: &Path) -> Result<u32> {
+    let mut file = File::open(path)?;
+    let mut hasher = Hasher::new();
+    let mut buffer = [0u8; 8192];
+
+    loop {
+        let read_size = file.read(&mut buffer)?;
+
+        if read_size == 0 {
+            break;
+        }
+
+        hasher.update(&buffer[..read_size]);
+    }
+
+    Ok(hasher.finalize())
+}
+
+/// Turns a core name like "snes9x" into the file name of the core for the current platform
+pub(crate) fn get_core_file_name(core: &str) -> String {
+    let suffix = format!("_libretro.{}", consts::DLL_EXTENSION);
+
+    if core.ends_with(&suffix) {
+        core.to_owned()
+    } else {
+        format!("{}{}", core.trim_end_matches("_libretro"), suffix)
+    }
+}
+
+pub(crate) fn get_installed_cores(core_path: &Path) -> Result<Vec<String>> {
+    list_core_files(core_path, consts::DLL_EXTENSION)
+}
+
+/// Lists the cores with the given library extension in a directory
+pub(crate) fn list_core_files(core_path: &Path, extension: &str) -> Result<Vec<String>> {
+    let suffix = format!("_libretro.{}", extension);
+    let mut cores = Vec::new();
+
+    if !core_path.exists() {
+        return Ok(cores);
+    }
+
+    for entry in read_dir(core_path)? {
+        let entry = entry?;
+
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+
+        if let Some(name) = entry.file_name().to_str()
+            && name.ends_with(&suffix)
+        {
+            cores.push(name.to_owned());
+        }
+    }
+
+    cores.sort();
+    Ok(cores)
+}
+
+pub(crate) fn extract_zip_file(file: &PathBuf, destination: &Path, message: &str) -> Result<()> {
+    extract_zip_file_with_mode(
+        file,
+        destination,
+        message,
+        &ExtractMode::Preserve { strip_prefix: None },
+    )
+}
+
+fn extract_zip_file_with_mode(
+    file: &PathBuf,
+    destination: &Path,
+    message: &str,
+    mode: &ExtractMode,
+) -> Result<()> {
     // Zip setup
     let zip_file = File::open(file)?;
-    let mut archive = ZipArchive::new(zip_file)?;
+    let mut archive = ZipArchive::new(zip_file).map_err(|error| invalid_archive(file, error))?;
+    let entry_paths = resolve_zip_entry_paths(&mut archive, file, destination, mode)?;
 
     let mut total_size = 0;
     for index in 0..archive.len() {
-        let file = archive.by_index(index)?;
+        let entry = archive
+            .by_index(index)
+            .map_err(|error| invalid_archive(file, error))?;
 
-        if file.is_file() {
-            total_size += file.size();
+        if entry_paths.contains_key(entry.name()) {
+            total_size += entry.size();
         }
     }
 
     // Indicatif setup
     let progress_bar = ProgressBar::new(total_size);
-    progress_bar.set_message(message);
+    progress_bar.set_message(message.to_owned());
 
     progress_bar.set_style(ProgressStyle::default_bar()
         .template("{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")?
@@ -129,22 +968,33 @@ fn extract_zip_file(file: &PathBuf, destination: &Path, message: &'static str) -
 
     let mut decompressed_size: u64 = 0;
 
-    // Extract archive
+    // Extract archive into a staging directory next to the destination
+    let staging = create_staging_dir(destination)?;
+
     for index in 0..archive.len() {
-        let mut file = archive.by_index(index)?;
+        // Cancelling drops the staging directory and leaves the destination untouched
+        check_cancelled()?;
+
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|error| invalid_archive(file, error))?;
 
-        if !file.is_file() {
+        let Some(entry_path) = entry_paths.get(entry.name()) else {
             continue;
-        }
+        };
 
         let mut buffer = [0u8; 1024];
-        let path = destination.join(file.name());
+        let path = staging.path().join(entry_path);
 
         std::fs::create_dir_all(path.parent().unwrap())?;
         let mut extracted_file = File::create(path)?;
 
         loop {
-            let read_size = file.read(&mut buffer)?;
+            check_cancelled()?;
+
+            let read_size = entry
+                .read(&mut buffer)
+                .map_err(|error| invalid_archive(file, error))?;
 
             if read_size == 0 {
                 break;
@@ -157,13 +1007,107 @@ fn extract_zip_file(file: &PathBuf, destination: &Path, message: &'static str) -
         }
     }
 
+    check_cancelled()?;
+
+    // Only move files into place once the whole archive was extracted
+    move_staged_files(staging.path(), destination)?;
+
     progress_bar.finish();
     Ok(())
 }
 
-fn extract_7zip_file(file: &PathBuf, destination: &Path, message: &'static str) -> Result<()> {
+/// How the entries of an archive are laid out in the destination
+#[derive(Clone, Debug)]
+pub(crate) enum ExtractMode {
+    /// Every file is written directly into the destination, like the cores of the core bundle
+    Flatten,
+    /// Directories are kept. Only entries below the prefix are extracted, without the prefix itself
+    Preserve { strip_prefix: Option<PathBuf> },
+}
+
+/// Extracts a .zip or .7z archive keeping its directory structure below the optional prefix
+pub(crate) fn extract_archive(
+    file: &PathBuf,
+    destination: &Path,
+    strip_prefix: Option<&Path>,
+    message: &str,
+) -> Result<()> {
+    let mode = ExtractMode::Preserve {
+        strip_prefix: strip_prefix.map(Path::to_path_buf),
+    };
+
+    if is_7zip_file(file) {
+        extract_7zip_file(file, destination, message, &mode, &[])?;
+        Ok(())
+    } else {
+        extract_zip_file_with_mode(file, destination, message, &mode)
+    }
+}
+
+/// Compares the files of a .zip or .7z archive below the optional prefix against the destination
+pub(crate) fn plan_archive_extraction(
+    file: &PathBuf,
+    destination: &Path,
+    strip_prefix: Option<&Path>,
+) -> Result<Vec<(PathBuf, ChangeStatus)>> {
+    let mode = ExtractMode::Preserve {
+        strip_prefix: strip_prefix.map(Path::to_path_buf),
+    };
+
+    if is_7zip_file(file) {
+        plan_7zip_extraction(file, destination, &mode)
+    } else {
+        plan_zip_extraction_with_mode(file, destination, &mode)
+    }
+}
+
+fn is_7zip_file(file: &Path) -> bool {
+    file.extension().is_some_and(|extension| extension == "7z")
+}
+
+fn plan_7zip_extraction(
+    file: &PathBuf,
+    destination: &Path,
+    mode: &ExtractMode,
+) -> Result<Vec<(PathBuf, ChangeStatus)>> {
+    let sz =
+        SevenZReader::open(file, Password::empty()).map_err(|error| map_7zip_error(file, error))?;
+    let entry_paths = resolve_7zip_entry_paths(&sz, file, destination, mode)?;
+    let mut changes = Vec::new();
+
+    for entry in sz.archive().files.iter().filter(|entry| entry.has_stream()) {
+        let Some(entry_path) = entry_paths.get(entry.name()) else {
+            continue;
+        };
+
+        let path = destination.join(entry_path);
+
+        // Entries without a checksum can't be compared and are always extracted
+        let status = if !path.exists() {
+            ChangeStatus::New
+        } else if entry.has_crc && get_file_crc(&path)? as u64 == entry.crc {
+            ChangeStatus::Unchanged
+        } else {
+            ChangeStatus::Updated
+        };
+
+        changes.push((path, status));
+    }
+
+    Ok(changes)
+}
+
+fn extract_7zip_file(
+    file: &PathBuf,
+    destination: &Path,
+    message: &str,
+    mode: &ExtractMode,
+    skip: &[String],
+) -> Result<Vec<PathBuf>> {
     // SevenZ setup
-    let mut sz = SevenZReader::open(file, Password::empty())?;
+    let mut sz =
+        SevenZReader::open(file, Password::empty()).map_err(|error| map_7zip_error(file, error))?;
+    let entry_paths = resolve_7zip_entry_paths(&sz, file, destination, mode)?;
 
     let total_size: u64 = sz
         .archive()
@@ -175,27 +1119,53 @@ fn extract_7zip_file(file: &PathBuf, destination: &Path, message: &'static str)
 
     // Indicatif setup
     let progress_bar = ProgressBar::new(total_size);
-    progress_bar.set_message(message);
+    progress_bar.set_message(message.to_owned());
 
     progress_bar.set_style(ProgressStyle::default_bar()
         .template("{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")?
         .progress_chars("#>-"));
 
     let mut decompressed_size: u64 = 0;
+    let mut extracted_paths = Vec::new();
+
+    // Extract archive into a staging directory next to the destination
+    let staging = create_staging_dir(destination)?;
 
-    // Extract archive
     sz.for_each_entries(|entry, reader| {
+        // Returning false stops the extraction, the cancellation is reported below
+        if is_cancelled() {
+            return Ok(false);
+        }
+
         if !entry.has_stream {
             return Ok(true);
         }
 
+        let entry_path = entry_paths
+            .get(entry.name())
+            .filter(|entry_path| !skip.iter().any(|name| *entry_path == Path::new(name)));
+
+        // Skipped entries still have to be read to advance the archive
+        let Some(entry_path) = entry_path else {
+            std::io::copy(reader, &mut std::io::sink())?;
+            decompressed_size += entry.size();
+            progress_bar.set_position(decompressed_size);
+
+            return Ok(true);
+        };
+
         let mut buffer = [0u8; 1024];
-        let path = destination.join(Path::new(entry.name()).file_name().unwrap());
+        let path = staging.path().join(entry_path);
+        extracted_paths.push(entry_path.to_owned());
 
         std::fs::create_dir_all(path.parent().unwrap())?;
         let mut file = File::create(path)?;
 
         loop {
+            if is_cancelled() {
+                break Ok(false);
+            }
+
             let read_size = reader.read(&mut buffer)?;
 
             if read_size == 0 {
@@ -207,8 +1177,535 @@ fn extract_7zip_file(file: &PathBuf, destination: &Path, message: &'static str)
             decompressed_size += read_size as u64;
             progress_bar.set_position(decompressed_size);
         }
-    })?;
+    })
+    .map_err(|error| map_7zip_error(file, error))?;
+
+    check_cancelled()?;
+
+    // Only move files into place once the whole archive was extracted
+    move_staged_files(staging.path(), destination)?;
 
     progress_bar.finish();
+    Ok(extracted_paths)
+}
+
+/// Blames the archive for errors while reading it, so damaged downloads get their own exit code
+fn invalid_archive(file: &Path, error: impl Display) -> anyhow::Error {
+    StarchError::InvalidArchive {
+        path: file.to_owned(),
+        reason: format!("it can't be read ({})", error),
+    }
+    .into()
+}
+
+/// Errors writing the extracted files are passed through the 7z reader as well, those aren't
+/// caused by the archive
+fn map_7zip_error(file: &Path, error: sevenz_rust::Error) -> anyhow::Error {
+    match error {
+        sevenz_rust::Error::Io(error, _) | sevenz_rust::Error::FileOpen(error, _) => {
+            if matches!(
+                error.kind(),
+                ErrorKind::InvalidData | ErrorKind::UnexpectedEof
+            ) {
+                invalid_archive(file, error)
+            } else {
+                error.into()
+            }
+        }
+        error => invalid_archive(file, error),
+    }
+}
+
+/// Turns an archive entry name into a relative path that can't leave the directory it is extracted to
+pub(crate) fn get_enclosed_path(name: &str) -> Option<PathBuf> {
+    // Archives created on Windows may use backslashes as separators
+    let name = name.replace('\\', "/");
+    let mut path = PathBuf::new();
+
+    for component in Path::new(&name).components() {
+        match component {
+            // Drive letters like "C:" would turn the path into an absolute one on Windows
+            Component::Normal(part) if has_drive_prefix(&part.to_string_lossy()) => return None,
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+/// Matches "C:", "C:foo" and everything else Windows would read as a drive or stream name
+fn has_drive_prefix(part: &str) -> bool {
+    let bytes = part.as_bytes();
+
+    part.ends_with(':') || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+}
+
+/// Resolves the entries of an archive to paths below the destination.
+/// Fails with a list of all offending entries if any of them would be written outside of it.
+fn resolve_entry_paths(
+    file: &Path,
+    destination: &Path,
+    entries: &[(String, bool)],
+    mode: &ExtractMode,
+) -> Result<HashMap<String, PathBuf>> {
+    let mut paths = HashMap::new();
+    let mut offending_entries = Vec::new();
+
+    for (name, is_symlink) in entries {
+        let Some(path) = get_enclosed_path(name) else {
+            offending_entries.push(format!("  {} (path leaves the destination)", name));
+            continue;
+        };
+
+        let path = match mode {
+            ExtractMode::Flatten => path.file_name().map(PathBuf::from),
+            ExtractMode::Preserve { strip_prefix: None } => Some(path),
+            ExtractMode::Preserve {
+                strip_prefix: Some(prefix),
+            } => path
+                .strip_prefix(prefix)
+                .ok()
+                .filter(|path| !path.as_os_str().is_empty())
+                .map(Path::to_path_buf),
+        };
+
+        // Entries outside of the stripped prefix are not extracted
+        let Some(path) = path else {
+            continue;
+        };
+
+        if *is_symlink {
+            offending_entries.push(format!("  {} (symbolic link)", name));
+            continue;
+        }
+
+        if let Some(link) = find_escaping_symlink(destination, &path)? {
+            offending_entries.push(format!(
+                "  {} (would be written through {}, which points outside the destination)",
+                name,
+                link.display()
+            ));
+            continue;
+        }
+
+        paths.insert(name.to_owned(), path);
+    }
+
+    if !offending_entries.is_empty() {
+        return Err(StarchError::InvalidArchive {
+            path: file.to_owned(),
+            reason: format!(
+                "it contains unsafe entries:\n{}",
+                offending_entries.join("\n")
+            ),
+        }
+        .into());
+    }
+
+    Ok(paths)
+}
+
+fn resolve_zip_entry_paths(
+    archive: &mut ZipArchive<File>,
+    file: &Path,
+    destination: &Path,
+    mode: &ExtractMode,
+) -> Result<HashMap<String, PathBuf>> {
+    let mut entries = Vec::new();
+
+    for index in 0..archive.len() {
+        let entry = archive
+            .by_index(index)
+            .map_err(|error| invalid_archive(file, error))?;
+
+        if !entry.is_dir() {
+            entries.push((entry.name().to_owned(), entry.is_symlink()));
+        }
+    }
+
+    resolve_entry_paths(file, destination, &entries, mode)
+}
+
+fn resolve_7zip_entry_paths(
+    sz: &SevenZReader<File>,
+    file: &Path,
+    destination: &Path,
+    mode: &ExtractMode,
+) -> Result<HashMap<String, PathBuf>> {
+    let entries: Vec<(String, bool)> = sz
+        .archive()
+        .files
+        .iter()
+        .filter(|entry| entry.has_stream())
+        .map(|entry| (entry.name().to_owned(), is_7zip_symlink(entry)))
+        .collect();
+
+    resolve_entry_paths(file, destination, &entries, mode)
+}
+
+fn is_7zip_symlink(entry: &SevenZArchiveEntry) -> bool {
+    const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+    const FILE_ATTRIBUTE_UNIX_EXTENSION: u32 = 0x8000;
+
+    if !entry.has_windows_attributes {
+        return false;
+    }
+
+    let attributes = entry.windows_attributes;
+    let unix_mode = attributes >> 16;
+
+    attributes & FILE_ATTRIBUTE_REPARSE_POINT != 0
+        || (attributes & FILE_ATTRIBUTE_UNIX_EXTENSION != 0 && unix_mode & 0o170000 == 0o120000)
+}
+
+/// Finds an existing directory symlink below the destination that would redirect the path outside of it
+fn find_escaping_symlink(destination: &Path, path: &Path) -> Result<Option<PathBuf>> {
+    let mut current = destination.to_path_buf();
+    let Some(parent) = path.parent() else {
+        return Ok(None);
+    };
+
+    for component in parent.components() {
+        current.push(component);
+
+        let is_symlink = current
+            .symlink_metadata()
+            .is_ok_and(|metadata| metadata.file_type().is_symlink());
+
+        if !is_symlink {
+            continue;
+        }
+
+        let escapes = match (current.canonicalize(), destination.canonicalize()) {
+            (Ok(target), Ok(destination)) => !target.starts_with(destination),
+            // Dangling links would be created wherever they point to
+            _ => true,
+        };
+
+        if escapes {
+            return Ok(Some(current));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Creates a temporary directory inside the destination so staged files can be renamed into place
+fn create_staging_dir(destination: &Path) -> Result<TempDir> {
+    std::fs::create_dir_all(destination)?;
+
+    Ok(Builder::new()
+        .prefix(".starch-staging-")
+        .tempdir_in(destination)?)
+}
+
+fn move_staged_files(staging: &Path, destination: &Path) -> Result<()> {
+    for entry in read_dir(staging)? {
+        let entry = entry?;
+        let path = destination.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            std::fs::create_dir_all(&path)?;
+            move_staged_files(&entry.path(), &path)?;
+        } else {
+            rename(entry.path(), path)?;
+        }
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(destination: &Path, entries: &[(&str, bool)]) -> Result<HashMap<String, PathBuf>> {
+        let entries: Vec<(String, bool)> = entries
+            .iter()
+            .map(|(name, is_symlink)| (name.to_string(), *is_symlink))
+            .collect();
+
+        resolve_entry_paths(
+            Path::new("test.zip"),
+            destination,
+            &entries,
+            &ExtractMode::Preserve { strip_prefix: None },
+        )
+    }
+
+    #[test]
+    fn enclosed_path_keeps_relative_paths() {
+        assert_eq!(
+            get_enclosed_path("cores/snes9x_libretro.so"),
+            Some(PathBuf::from("cores/snes9x_libretro.so"))
+        );
+        assert_eq!(get_enclosed_path("./a/./b"), Some(PathBuf::from("a/b")));
+    }
+
+    #[test]
+    fn enclosed_path_rejects_parent_directories() {
+        assert_eq!(get_enclosed_path("../evil.so"), None);
+        assert_eq!(get_enclosed_path("cores/../../evil.so"), None);
+        assert_eq!(get_enclosed_path("cores/.."), None);
+    }
+
+    #[test]
+    fn enclosed_path_rejects_absolute_paths() {
+        assert_eq!(get_enclosed_path("/etc/passwd"), None);
+        assert_eq!(get_enclosed_path("//server/share/evil.dll"), None);
+    }
+
+    #[test]
+    fn enclosed_path_handles_backslashes() {
+        assert_eq!(
+            get_enclosed_path("cores\\snes9x_libretro.dll"),
+            Some(PathBuf::from("cores/snes9x_libretro.dll"))
+        );
+        assert_eq!(get_enclosed_path("..\\..\\evil.dll"), None);
+        assert_eq!(get_enclosed_path("\\Windows\\evil.dll"), None);
+    }
+
+    #[test]
+    fn enclosed_path_rejects_drive_prefixes() {
+        assert_eq!(get_enclosed_path("C:\\Windows\\evil.dll"), None);
+        assert_eq!(get_enclosed_path("C:/Windows/evil.dll"), None);
+        assert_eq!(get_enclosed_path("C:evil.dll"), None);
+        assert_eq!(get_enclosed_path("cores/d:"), None);
+    }
+
+    #[test]
+    fn enclosed_path_rejects_empty_paths() {
+        assert_eq!(get_enclosed_path(""), None);
+        assert_eq!(get_enclosed_path("./"), None);
+    }
+
+    #[test]
+    fn resolve_lists_all_unsafe_entries() {
+        let destination = tempfile::tempdir().unwrap();
+        let error = resolve(
+            destination.path(),
+            &[("ok.so", false), ("../evil.so", false), ("/abs.so", false)],
+        )
+        .unwrap_err()
+        .to_string();
+
+        assert!(error.contains("../evil.so (path leaves the destination)"));
+        assert!(error.contains("/abs.so (path leaves the destination)"));
+        assert!(!error.contains("ok.so"));
+    }
+
+    #[test]
+    fn resolve_rejects_symlink_entries() {
+        let destination = tempfile::tempdir().unwrap();
+        let error = resolve(destination.path(), &[("link", true)])
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.contains("link (symbolic link)"));
+    }
+
+    #[test]
+    fn resolve_applies_extract_modes() {
+        let destination = tempfile::tempdir().unwrap();
+        let entries = vec![
+            ("RetroArch/cores/a.so".to_owned(), false),
+            ("other/b.so".to_owned(), false),
+        ];
+
+        let flat = resolve_entry_paths(
+            Path::new("test.7z"),
+            destination.path(),
+            &entries,
+            &ExtractMode::Flatten,
+        )
+        .unwrap();
+        assert_eq!(flat["RetroArch/cores/a.so"], PathBuf::from("a.so"));
+        assert_eq!(flat["other/b.so"], PathBuf::from("b.so"));
+
+        let stripped = resolve_entry_paths(
+            Path::new("test.7z"),
+            destination.path(),
+            &entries,
+            &ExtractMode::Preserve {
+                strip_prefix: Some(PathBuf::from("RetroArch")),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            stripped["RetroArch/cores/a.so"],
+            PathBuf::from("cores/a.so")
+        );
+        assert!(!stripped.contains_key("other/b.so"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_rejects_paths_through_escaping_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let destination = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        symlink(outside.path(), destination.path().join("out")).unwrap();
+        symlink("/nonexistent", destination.path().join("dangling")).unwrap();
+
+        let error = resolve(
+            destination.path(),
+            &[("out/evil.so", false), ("dangling/evil.so", false)],
+        )
+        .unwrap_err()
+        .to_string();
+
+        assert!(error.contains("out/evil.so (would be written through"));
+        assert!(error.contains("dangling/evil.so (would be written through"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_allows_symlinks_within_the_destination() {
+        use std::os::unix::fs::symlink;
+
+        let destination = tempfile::tempdir().unwrap();
+        std::fs::create_dir(destination.path().join("real")).unwrap();
+        symlink(
+            destination.path().join("real"),
+            destination.path().join("alias"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            find_escaping_symlink(destination.path(), Path::new("alias/core.so")).unwrap(),
+            None
+        );
+        assert!(resolve(destination.path(), &[("alias/core.so", false)]).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn find_escaping_symlink_reports_the_link() {
+        use std::os::unix::fs::symlink;
+
+        let destination = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::create_dir(destination.path().join("cores")).unwrap();
+        symlink(outside.path(), destination.path().join("cores/out")).unwrap();
+
+        assert_eq!(
+            find_escaping_symlink(destination.path(), Path::new("cores/out/evil.so")).unwrap(),
+            Some(destination.path().join("cores/out"))
+        );
+        assert_eq!(
+            find_escaping_symlink(destination.path(), Path::new("cores/fine.so")).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn extract_archive_strips_prefix() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        let archive_path = source.path().join("shaders.zip");
+
+        let mut writer = zip::ZipWriter::new(File::create(&archive_path).unwrap());
+        for name in ["shaders/crt/crt.slangp", "README.md"] {
+            writer
+                .start_file(name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(name.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let prefix = Some(Path::new("shaders"));
+        let changes = plan_archive_extraction(&archive_path, destination.path(), prefix).unwrap();
+        assert_eq!(
+            changes,
+            vec![(destination.path().join("crt/crt.slangp"), ChangeStatus::New)]
+        );
+
+        extract_archive(&archive_path, destination.path(), prefix, "Extracting...").unwrap();
+        assert!(destination.path().join("crt/crt.slangp").is_file());
+        assert!(!destination.path().join("README.md").exists());
+        assert!(!destination.path().join("shaders").exists());
+    }
+
+    #[test]
+    fn extract_7zip_archive_preserves_directories() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        let archive_path = source.path().join("shaders.7z");
+
+        // Same-named files in different folders would collide if they got flattened
+        let files = [
+            ("RetroArch/crt/shader.slangp", "crt"),
+            ("RetroArch/lcd/shader.slangp", "lcd"),
+            ("RetroArch/README.md", "readme"),
+            ("Other/shader.slangp", "other"),
+        ];
+
+        let mut writer = sevenz_rust::SevenZWriter::create(&archive_path).unwrap();
+        for (name, contents) in files {
+            let mut entry = sevenz_rust::SevenZArchiveEntry::new();
+            entry.name = name.to_owned();
+            entry.has_stream = true;
+
+            writer
+                .push_archive_entry(entry, Some(contents.as_bytes()))
+                .unwrap();
+        }
+        writer.finish().unwrap();
+
+        let prefix = Some(Path::new("RetroArch"));
+        let mut changes =
+            plan_archive_extraction(&archive_path, destination.path(), prefix).unwrap();
+        changes.sort_by(|(a, _), (b, _)| a.cmp(b));
+        assert_eq!(
+            changes,
+            vec![
+                (destination.path().join("README.md"), ChangeStatus::New),
+                (
+                    destination.path().join("crt/shader.slangp"),
+                    ChangeStatus::New
+                ),
+                (
+                    destination.path().join("lcd/shader.slangp"),
+                    ChangeStatus::New
+                ),
+            ]
+        );
+
+        extract_archive(&archive_path, destination.path(), prefix, "Extracting...").unwrap();
+        let read = |path: &str| std::fs::read_to_string(destination.path().join(path)).unwrap();
+        assert_eq!(read("crt/shader.slangp"), "crt");
+        assert_eq!(read("lcd/shader.slangp"), "lcd");
+        assert_eq!(read("README.md"), "readme");
+        assert!(!destination.path().join("shader.slangp").exists());
+        assert!(!destination.path().join("Other").exists());
+        assert!(!destination.path().join("RetroArch").exists());
+    }
+
+    #[test]
+    fn damaged_archives_are_invalid() {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        let archive_path = source.path().join("cores.zip");
+
+        let mut writer = zip::ZipWriter::new(File::create(&archive_path).unwrap());
+        writer
+            .start_file("a_libretro.so", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(&[0; 4096]).unwrap();
+        writer.finish().unwrap();
+
+        // Cut off the central directory like an interrupted download would
+        let bytes = std::fs::read(&archive_path).unwrap();
+        std::fs::write(&archive_path, &bytes[..bytes.len() / 2]).unwrap();
+
+        let error =
+            extract_zip_file(&archive_path, destination.path(), "Extracting...").unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<StarchError>(),
+            Some(StarchError::InvalidArchive { .. })
+        ));
+    }
+}